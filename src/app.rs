@@ -1,4 +1,170 @@
-use std::{collections::HashMap, sync::mpsc};
+use std::{collections::HashMap, path::PathBuf, sync::mpsc, time::Duration};
+
+use chrono::{
+    DateTime, Datelike, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+    Timelike, Utc,
+};
+use chrono_tz::Tz;
+
+#[cfg(any(windows, target_os = "macos"))]
+use crate::tray::{Tray, TrayAction};
+use crate::{
+    core::{Core, SubtaskNode, SubtaskPath, Todo, DEFAULT_STICKY_SIZE, MAX_LABEL_CHARS},
+    encryption::{self, Cipher, EncryptedState},
+    highlight,
+    i18n::{Lang, Text},
+    import, natural_date, recovery,
+    recurrence::Recurrence,
+    spellcheck::{self, Dictionary},
+};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{email::EmailSettings, file_storage::FileStorage};
+
+const CELEBRATION_SECONDS: f32 = 0.6;
+const FLIP_SECONDS: f32 = 0.3;
+const PEEL_SECONDS: f32 = 0.35;
+/// How long a sticky flashes for when its alarm goes off.
+const POP_SECONDS: f32 = 1.5;
+/// How opaque stickies are while the pointer is elsewhere, with `Settings::fade_idle`.
+const IDLE_OPACITY: f32 = 0.6;
+const MIN_EXPORT_WIDTH: usize = 20;
+const UNDO_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+const SAVE_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::S);
+/// Adds the draft as a sticky, with `Settings::add_with_shortcut`.
+const ADD_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Enter);
+const REDO_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(
+    egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT),
+    egui::Key::Z,
+);
+/// How many undo steps are kept before the oldest is dropped.
+const UNDO_LIMIT: usize = 100;
+/// Storage key for the last saved state that couldn't be read, backed up before saving over it.
+const UNREADABLE_STATE_KEY: &str = "unreadable_app";
+const SEARCH_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::F);
+const GO_TO_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::G);
+/// Hides or shows the top bar and status bar.
+const CHROME_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::NONE, egui::Key::F11);
+const STICKY_COLORS: [(&str, egui::Color32); 8] = [
+    ("Yellow", egui::Color32::from_rgb(255, 235, 132)),
+    ("Orange", egui::Color32::from_rgb(255, 189, 122)),
+    ("Pink", egui::Color32::from_rgb(255, 168, 196)),
+    ("Green", egui::Color32::from_rgb(178, 232, 160)),
+    ("Blue", egui::Color32::from_rgb(160, 210, 255)),
+    ("Purple", egui::Color32::from_rgb(120, 80, 170)),
+    ("Navy", egui::Color32::from_rgb(30, 50, 100)),
+    ("Charcoal", egui::Color32::from_rgb(50, 50, 55)),
+];
+/// The choices offered for `Settings::auto_archive_after`.
+const AUTO_ARCHIVE_DELAYS: [Option<Duration>; 4] = [
+    None,
+    Some(Duration::from_secs(60 * 60)),
+    Some(Duration::from_secs(24 * 60 * 60)),
+    Some(Duration::from_secs(7 * 24 * 60 * 60)),
+];
+/// The choices offered for `Settings::page_size`.
+const PAGE_SIZES: [Option<usize>; 4] = [None, Some(25), Some(50), Some(100)];
+/// How close, in points, a dragged sticky's edge must be to another's to align with it.
+const SNAP_DISTANCE: f32 = 6.0;
+/// Common repeat rules offered before the custom RRULE field.
+const REPEAT_PRESETS: [(&str, Option<&str>); 6] = [
+    ("Never", None),
+    ("Daily", Some("FREQ=DAILY")),
+    ("Weekdays", Some("FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR")),
+    ("Weekly", Some("FREQ=WEEKLY")),
+    ("Every 2 weeks", Some("FREQ=WEEKLY;INTERVAL=2")),
+    ("Monthly", Some("FREQ=MONTHLY")),
+];
+/// Alarm delays offered from a sticky's menu, in minutes.
+const ALARM_DELAYS: [(&str, i64); 4] = [
+    ("In 5 minutes", 5),
+    ("In 15 minutes", 15),
+    ("In 1 hour", 60),
+    ("Tomorrow", 24 * 60),
+];
+/// How many more times an alarm can go off after the first, and how many minutes apart.
+const REMINDER_REPEATS: [(&str, u32, u64); 3] = [
+    ("3 more times, 5 minutes apart", 3, 5),
+    ("5 more times, 15 minutes apart", 5, 15),
+    ("8 more times, an hour apart", 8, 60),
+];
+/// How long a sticky can be snoozed for, in minutes.
+const SNOOZE_DELAYS: [(&str, i64); 4] = [
+    ("For 1 hour", 60),
+    ("For 3 hours", 3 * 60),
+    ("Until tomorrow", 24 * 60),
+    ("For a week", 7 * 24 * 60),
+];
+/// Story point estimates a sticky's effort can be set to.
+const EFFORT_POINTS: [u8; 6] = [1, 2, 3, 5, 8, 13];
+/// Built-in sets of stickies offered under "From template". Labels and subtasks can hold the
+/// placeholders described at `expand_placeholders`.
+const TEMPLATES: [Template; 5] = [
+    Template {
+        name: "Grocery list",
+        stickies: &[(
+            "Groceries",
+            &["Milk", "Eggs", "Bread", "Fruit", "Vegetables"],
+        )],
+    },
+    Template {
+        name: "Daily standup",
+        stickies: &[(
+            "Standup",
+            &["What I did yesterday", "What I'm doing today", "Blockers"],
+        )],
+    },
+    Template {
+        name: "Journal entry",
+        stickies: &[(
+            "Journal {weekday} {date}",
+            &["Grateful for", "Today's focus", "On my mind"],
+        )],
+    },
+    Template {
+        name: "Weekly chores",
+        stickies: &[
+            ("Laundry", &[]),
+            ("Vacuum", &[]),
+            ("Take out the trash", &[]),
+            ("Water the plants", &[]),
+        ],
+    },
+    Template {
+        name: "Trip packing",
+        stickies: &[(
+            "Packing",
+            &["Passport", "Chargers", "Toiletries", "Clothes"],
+        )],
+    },
+];
+/// Content sizes a sticky can be switched between.
+const STICKY_SIZES: [(&str, egui::Vec2); 3] = [
+    ("Small", egui::vec2(120.0, 120.0)),
+    ("Medium", DEFAULT_STICKY_SIZE),
+    ("Large", egui::vec2(220.0, 220.0)),
+];
+/// Width to height ratios stickies can be locked to when they're resized.
+const ASPECT_RATIOS: [(&str, Option<f32>); 4] = [
+    ("Free", None),
+    ("Square", Some(1.0)),
+    ("Wide", Some(4.0 / 3.0)),
+    ("Tall", Some(3.0 / 4.0)),
+];
+/// The least opaque the app's window can be made, so its text stays readable.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))] // web pages have their own background
+const MIN_WINDOW_OPACITY: f32 = 0.3;
+/// How much smaller stickies, their text and their spacing are in compact view.
+const COMPACT_SCALE: f32 = 0.75;
+const ZONE_HEADER_HEIGHT: f32 = 24.0;
+/// The strip along a sticky's top that it's dragged by.
+const DRAG_HANDLE_HEIGHT: f32 = 8.0;
+const MIN_ZONE_SIZE: egui::Vec2 = egui::vec2(120.0, 80.0);
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -9,9 +175,160 @@ pub struct AppState {
     #[serde(skip_serializing, skip_deserializing)]
     effects_rx: mpsc::Receiver<Effect>,
 
-    draft: String,
-    todos: Vec<Todo>,
+    core: Core,
+    /// State saved before `core` existed kept these at the top level, see `migrate`.
+    #[serde(
+        rename = "draft",
+        skip_serializing,
+        deserialize_with = "deserialize_some"
+    )]
+    legacy_draft: Option<String>,
+    #[serde(
+        rename = "todos",
+        skip_serializing,
+        deserialize_with = "deserialize_some"
+    )]
+    legacy_todos: Option<Vec<Todo>>,
     calculated: HashMap<String, f32>,
+    view: ViewMode,
+    completion_filter: CompletionFilter,
+    settings: Settings,
+    zones: Vec<Zone>,
+    boards: Vec<Board>,
+    /// Index into `boards` of the board being shown.
+    active_board: usize,
+    #[serde(skip)]
+    undo_stack: Vec<UndoStep>,
+    /// The todos and boards as they were before each undo, cleared by any new undoable change.
+    #[serde(skip)]
+    redo_stack: Vec<UndoStep>,
+    /// The sticky last clicked on the canvas, which keyboard shortcuts act on. Saved so it's
+    /// still focused after a restart.
+    focused_todo: Option<egui::Id>,
+    /// How far the list view is scrolled, saved to scroll back there after a restart.
+    list_scroll: f32,
+    /// The zoom last used on each monitor, by `monitor_key`, so moving the window between
+    /// screens of different DPI puts back the zoom picked for each.
+    monitor_zoom: HashMap<String, f32>,
+    /// The `monitor_key` of the monitor the window was on last frame.
+    #[serde(skip)]
+    monitor: Option<String>,
+    /// Whether the first frame since loading has been shown, which restores the saved focus
+    /// and scroll position.
+    #[serde(skip)]
+    session_restored: bool,
+    /// Whether anything saved has changed since the last save.
+    #[serde(skip)]
+    unsaved: bool,
+    /// Set by Ctrl+S or the save status button, to save at the end of the frame rather than
+    /// waiting for eframe's next autosave.
+    #[serde(skip)]
+    save_requested: bool,
+    /// Stickies whose label was edited since the last save, marked while they're edited.
+    #[serde(skip)]
+    unsaved_labels: Vec<egui::Id>,
+    /// Stickies playing their peel animation, which are deleted once it finishes.
+    #[serde(skip)]
+    peeling: Vec<egui::Id>,
+    /// Stickies that have been raised and are flashing for attention.
+    #[serde(skip)]
+    popping: Vec<egui::Id>,
+    /// Which page of stickies is shown, when `Settings::page_size` limits them.
+    #[serde(skip)]
+    page: usize,
+    #[serde(skip)]
+    confirming_reset: bool,
+    /// Whether deleting every sticky the filters show is waiting on a confirmation.
+    #[serde(skip)]
+    confirming_delete_shown: bool,
+    /// The tag typed to add to every sticky the filters show.
+    #[serde(skip)]
+    shown_tag_draft: String,
+    /// The todo being checked off while it still has open subtasks, waiting on a confirmation.
+    #[serde(skip)]
+    confirming_check: Option<egui::Id>,
+    /// Saved with the completion filter, but only restored with `Settings::remember_filters`.
+    search_open: bool,
+    search_query: String,
+    /// Whether the search query is a regular expression rather than text to find.
+    search_regex: bool,
+    /// The search query compiled, while `search_regex` is on.
+    #[serde(skip)]
+    search_pattern: Option<Result<regex::Regex, regex::Error>>,
+    /// The todo shown in full by the detail view.
+    #[serde(skip)]
+    selected_detail: Option<egui::Id>,
+    /// Whether the weekly review panel is shown.
+    #[serde(skip)]
+    review_open: bool,
+    /// Whether the agenda of upcoming alarms is shown.
+    #[serde(skip)]
+    agenda_open: bool,
+    /// Whether the top bar and status bar are hidden, leaving the board to itself. Kept across
+    /// restarts, for those who'd rather always have it that way.
+    chrome_hidden: bool,
+    /// The sticky shown alone and enlarged over everything else, while focus mode is on.
+    #[serde(skip)]
+    focus_mode: Option<egui::Id>,
+    /// The subtask just added to a sticky being edited, whose field takes focus when it's first
+    /// shown.
+    #[serde(skip)]
+    new_subtask: Option<(egui::Id, SubtaskPath)>,
+    /// The number typed into the "Go to sticky" dialog, while it's open.
+    #[serde(skip)]
+    go_to_draft: Option<String>,
+
+    /// Encrypted state loaded on startup, waiting for the passphrase.
+    #[serde(skip)]
+    locked: Option<EncryptedState>,
+    /// The key saves are encrypted with, when encryption is turned on.
+    #[serde(skip)]
+    cipher: Option<Cipher>,
+    #[serde(skip)]
+    passphrase_draft: String,
+    #[serde(skip)]
+    encryption_dialog_open: bool,
+    #[serde(skip)]
+    encryption_error: Option<String>,
+    /// Set when saved state didn't fully load.
+    #[serde(skip)]
+    recovery: Option<Recovery>,
+    /// Labels passed on the command line, added as stickies once the state is unlocked.
+    #[serde(skip)]
+    launch_labels: Vec<String>,
+    /// Where to save the screenshot requested by "Save as image", once it arrives.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))] // no file system access on web
+    #[serde(skip)]
+    screenshot_path: Option<PathBuf>,
+    /// The image behind the canvas and the path it was loaded from, `None` if it didn't load.
+    #[serde(skip)]
+    canvas_texture: Option<(PathBuf, Option<egui::TextureHandle>)>,
+
+    /// Where state is saved when launched with `--storage <file>`, instead of eframe's storage.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    custom_storage: Option<FileStorage>,
+    /// Whether `custom_storage` changed on disk since it was loaded or saved, which holds off
+    /// saving until the user picks which to keep.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    storage_changed: bool,
+    /// Why the last reminder email failed to send, until dismissed.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    email_error: Option<String>,
+    /// Why the last sticky exported on its way off the board couldn't be written, until
+    /// dismissed.
+    #[serde(skip)]
+    export_error: Option<String>,
+    /// The window's title as last set, counting the stickies due today.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    window_title: String,
+    /// The icon in the system tray, `None` if it couldn't be added.
+    #[cfg(any(windows, target_os = "macos"))]
+    #[serde(skip)]
+    tray: Option<Tray>,
 }
 
 impl Default for AppState {
@@ -23,8 +340,65 @@ impl Default for AppState {
             effects_rx,
             calculated: HashMap::new(),
 
-            draft: "Feed doge".to_owned(),
-            todos: vec![],
+            core: Core::default(),
+            legacy_draft: None,
+            legacy_todos: None,
+            view: ViewMode::default(),
+            completion_filter: CompletionFilter::default(),
+            settings: Settings::default(),
+            zones: vec![],
+            boards: vec![Board::new("My board".to_owned())],
+            active_board: 0,
+            undo_stack: vec![],
+            redo_stack: vec![],
+            focused_todo: None,
+            list_scroll: 0.0,
+            monitor_zoom: HashMap::new(),
+            monitor: None,
+            session_restored: false,
+            unsaved: false,
+            save_requested: false,
+            unsaved_labels: vec![],
+            peeling: vec![],
+            popping: vec![],
+            page: 0,
+            confirming_reset: false,
+            confirming_delete_shown: false,
+            shown_tag_draft: String::new(),
+            confirming_check: None,
+            search_open: false,
+            search_query: String::new(),
+            search_regex: false,
+            search_pattern: None,
+            selected_detail: None,
+            review_open: false,
+            agenda_open: false,
+            chrome_hidden: false,
+            focus_mode: None,
+            new_subtask: None,
+            go_to_draft: None,
+
+            locked: None,
+            cipher: None,
+            passphrase_draft: String::new(),
+            encryption_dialog_open: false,
+            encryption_error: None,
+            recovery: None,
+            launch_labels: vec![],
+            screenshot_path: None,
+            canvas_texture: None,
+
+            #[cfg(not(target_arch = "wasm32"))]
+            custom_storage: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            storage_changed: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            email_error: None,
+            export_error: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            window_title: String::new(),
+            #[cfg(any(windows, target_os = "macos"))]
+            tray: None,
         }
     }
 }
@@ -35,280 +409,6679 @@ impl AppState {
         // This is also where you can customize the look and feel of egui using
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
 
+        #[allow(unused_mut)] // only native builds take arguments
+        let mut state = Self::open(cc);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            state.launch_labels = label_args(std::env::args().skip(1));
+        }
+        #[cfg(any(windows, target_os = "macos"))]
+        {
+            state.tray = Tray::new(&cc.egui_ctx);
+        }
+        state
+    }
+
+    fn open(cc: &eframe::CreationContext<'_>) -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = storage_path_arg() {
+            match FileStorage::open(path.clone()) {
+                Ok(mut storage) => {
+                    storage.watch(&cc.egui_ctx);
+                    let state = if start_fresh(cc) {
+                        Self::default()
+                    } else {
+                        Self::load(&storage)
+                    };
+                    return Self {
+                        custom_storage: Some(storage),
+                        ..state
+                    };
+                }
+                Err(err) => log::error!(
+                    "Can't store stickies in {}, using the default location instead: {err}",
+                    path.display()
+                ),
+            }
+        }
+
+        // Skip loading for demos or to recover from a corrupted store. The empty board
+        // replaces the persisted one on the next save.
+        if start_fresh(cc) {
+            log::info!("Starting with an empty board, ignoring persisted state");
+            return Default::default();
+        }
+
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
         if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+            return Self::load(storage);
         }
 
         Default::default()
     }
 
-    fn apply_effects(&mut self, ctx: &egui::Context) {
-        while let Ok(effect) = self.effects_rx.try_recv() {
-            match effect {
-                Effect::DraftTodo(draft) => {
-                    self.draft = draft;
-                }
-                Effect::AddTodo(label) => {
-                    let id = ctx.memory_mut(|mem| {
-                        let counter: &mut usize = mem
-                            .data
-                            .get_persisted_mut_or_default(egui::Id::new("counter"));
-                        let id = egui::Id::new(*counter);
-                        *counter += 1;
-                        id
-                    });
+    fn load(storage: &dyn eframe::Storage) -> Self {
+        // Encrypted state stays locked until the user enters their passphrase
+        if let Some(encrypted) = eframe::get_value(storage, encryption::ENCRYPTED_KEY) {
+            return Self {
+                locked: Some(encrypted),
+                ..Default::default()
+            };
+        }
 
-                    self.todos.push(Todo::new(id, label));
-                    self.draft.clear();
-                }
-                Effect::EditTodo(index) => {
-                    if let Some(todo) = self.todos.get_mut(index) {
-                        todo.edit_mode = !todo.edit_mode;
-                    }
-                }
-                Effect::SaveTodo(index, label) => {
-                    if let Some(todo) = self.todos.get_mut(index) {
-                        todo.label = label;
-                    }
-                }
-                Effect::CheckTodo(index) => {
-                    if let Some(todo) = self.todos.get_mut(index) {
-                        todo.checked = !todo.checked;
-                    }
-                }
-                Effect::DeleteTodo(index) => {
-                    if index < self.todos.len() {
-                        self.todos.remove(index);
-                    }
-                }
+        match storage.get_string(eframe::APP_KEY) {
+            // Blank while encryption is on, see `save_to`
+            Some(text) if !text.is_empty() => Self::parse_saved(&text),
+            _ => Self::default(),
+        }
+    }
 
-                Effect::InsertCalculated(name, value) => {
-                    self.calculated.insert(name, value);
-                    // self.calculated.clear();
-                }
-            }
+    fn parse_saved(text: &str) -> Self {
+        let mut state = Self::parse_or_salvage(text);
+        if !state.settings.remember_filters {
+            state.completion_filter = CompletionFilter::default();
+            state.search_open = false;
+            state.search_query.clear();
+            state.search_regex = false;
         }
+        if let Some(board) = state.settings.startup_board {
+            // Falls back to the first board if the picked one is gone
+            state.active_board = if board < state.boards.len() { board } else { 0 };
+        }
+        state.compile_search();
+        state
     }
 
-    fn render(&self, ctx: &egui::Context) {
-        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-            egui::menu::bar(ui, |ui| {
-                egui::widgets::global_dark_light_mode_buttons(ui);
-            });
-        });
+    /// Parses saved state, salvaging what it can if it doesn't parse as a whole, rather than
+    /// silently starting over. The original text is backed up so nothing is lost for good.
+    fn parse_or_salvage(text: &str) -> Self {
+        let err = match ron::from_str::<Self>(text) {
+            Ok(state) => return state.migrate(),
+            Err(err) => err,
+        };
+        log::error!("Saved state didn't parse, salvaging what can be read: {err}");
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.vertical_centered(|ui| {
-                ui.heading("Stickies");
-            });
+        let state = recovery::lenient_struct::<Self>(text, &salvage_saved_field)
+            .map(Self::migrate)
+            .unwrap_or_default();
+        Self {
+            recovery: Some(Recovery {
+                original: text.to_owned(),
+                #[cfg(not(target_arch = "wasm32"))]
+                backup_path: backup_unreadable_state(text),
+                recovered_todos: state.core.todos.len(),
+                dismissed: false,
+            }),
+            ..state
+        }
+    }
 
-            ui.add_space(10.0);
+    /// Adds a board exported with "Export board…", with its stickies, and switches to it.
+    fn import_board(&mut self, ctx: &egui::Context, path: &std::path::Path) {
+        let file = std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| {
+                serde_json::from_str::<BoardFile>(&text).map_err(|err| err.to_string())
+            });
+        let file = match file {
+            Ok(file) => file,
+            Err(err) => {
+                log::warn!("Failed to import {}: {err}", path.display());
+                return;
+            }
+        };
 
-            ui.horizontal(|ui| {
-                // Center the elements using the stored width from the previous frame
-                // TODO: to prevent flicker, the first frame should only calculate size and not actually render
-                // Can use Juan's helper: https://gist.github.com/juancampa/faf3525beefa477babdad237f5e81ffe
-                let id = "draft_todo";
-                if let Some(stored_width) = self.calculated.get(id) {
-                    let offset = (ui.available_width() - stored_width) / 2.0;
-                    ui.add_space(offset);
-                }
+        let index = self.boards.len();
+        self.boards.push(file.board);
+        let todos = file
+            .stickies
+            .into_iter()
+            .map(|sticky| Todo {
+                initial_position: sticky.position,
+                ..sticky.todo
+            })
+            .collect();
+        self.core.import_board(index, todos, || next_todo_id(ctx));
+        self.active_board = index;
+        self.page = 0;
+    }
 
-                ui.label("Add a sticky: ");
+    /// Adds a sticky to the active board for each note in a text export, cascading them so
+    /// they don't hide each other.
+    fn import_stickies(&mut self, ctx: &egui::Context, path: &std::path::Path) {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) => {
+                log::warn!("Failed to import {}: {err}", path.display());
+                return;
+            }
+        };
 
-                // TODO: wrap immutable reference in a Cow instead of creating local copy explicitly
-                let mut local_draft = self.draft.clone();
-                if ui.text_edit_singleline(&mut local_draft).lost_focus()
-                    && ui.input(|i| i.key_pressed(egui::Key::Enter))
-                {
-                    self.effects_tx
-                        .send(Effect::AddTodo(local_draft.clone()))
-                        .unwrap();
-                    local_draft.clear();
-                }
+        let mut position = egui::pos2(40.0, 120.0);
+        for note in import::parse_notes(&text) {
+            let mut todo = Todo::new(next_todo_id(ctx), self.active_board, note.label);
+            todo.back = note.back;
+            todo.color = note.color.as_deref().and_then(sticky_color_named);
+            todo.initial_position = Some(position);
+            todo.z = self.core.next_z();
+            self.core.todos.push(todo);
+            position += egui::vec2(24.0, 24.0);
+        }
+    }
 
-                if ui.button("Save").clicked() {
-                    self.effects_tx
-                        .send(Effect::AddTodo(local_draft.clone()))
-                        .unwrap();
-                    local_draft.clear();
-                }
+    /// Brings the sticky with `id` to the front, both on screen and in the persisted stack.
+    fn raise_todo(&mut self, ctx: &egui::Context, id: egui::Id) {
+        if let Some(index) = self.core.todos.iter().position(|todo| todo.id == id) {
+            self.core.raise(index);
+        }
+        ctx.move_to_top(egui::LayerId::new(egui::Order::Middle, id));
+    }
 
-                // TODO: only send effect if Cow is Owned variant (because that means it made a copy upon editing the input)
-                self.effects_tx
-                    .send(Effect::DraftTodo(local_draft))
-                    .unwrap();
+    /// Picks up where the last session left off. The list view's scroll position is restored
+    /// as it renders.
+    fn restore_session(&mut self) {
+        // Deleting a sticky doesn't clear the focus, so the saved id may be gone
+        let todos = &self.core.todos;
+        self.focused_todo = self
+            .focused_todo
+            .filter(|id| todos.iter().any(|todo| todo.id == *id));
+    }
 
-                // Store the width for the next frame if this is the first frame
-                if self.calculated.get(id).is_none() {
-                    self.effects_tx
-                        .send(Effect::InsertCalculated(
-                            id.to_string(),
-                            ui.min_rect().width(),
-                        ))
-                        .unwrap();
-                }
-            });
+    /// Switches to the zoom last used on the monitor the window just moved to, and otherwise
+    /// remembers the zoom in use for the current monitor. On a monitor seen for the first time,
+    /// or one that can't be told apart, the zoom stays as it was.
+    fn track_monitor_zoom(&mut self, ctx: &egui::Context) {
+        let Some(key) = ctx.input(|i| monitor_key(i.viewport())) else {
+            return;
+        };
+        if self.monitor.as_ref() == Some(&key) {
+            let zoom = ctx.zoom_factor();
+            if self.monitor_zoom.get(&key) != Some(&zoom) {
+                self.monitor_zoom.insert(key, zoom);
+            }
+        } else {
+            // The new zoom takes effect next frame, which is when it's next remembered
+            if let Some(&zoom) = self.monitor_zoom.get(&key) {
+                ctx.set_zoom_factor(zoom);
+            }
+            self.monitor = Some(key);
+        }
+    }
 
-            ui.add_space(10.0);
+    /// `label` as it's saved once editing ends, tidied with `Settings::tidy_labels`.
+    fn saved_label(&self, label: String) -> String {
+        if self.settings.tidy_labels {
+            tidy_label(&label)
+        } else {
+            label
+        }
+    }
 
-            for (index, todo) in self.todos.iter().enumerate() {
-                let window = egui::Window::new(todo.label.clone())
-                    .id(todo.id)
-                    .resizable(false)
-                    .collapsible(false)
-                    .title_bar(false);
+    /// The label without the date phrase it ends with, and the alarm the phrase sets, with
+    /// `Settings::alarms_from_labels`.
+    fn label_alarm(&self, label: &str) -> Option<(String, DateTime<Utc>)> {
+        if !self.settings.alarms_from_labels {
+            return None;
+        }
+        let now = self.settings.local(Utc::now()).naive_local();
+        let (label, when) = natural_date::split_trailing_date(label, now)?;
+        // A time skipped or repeated by a DST change is ambiguous, so it's left in the label
+        let alarm = self.settings.utc(when)?;
+        Some((label.to_owned(), alarm))
+    }
 
-                window.show(ui.ctx(), |ui| {
-                    // Note: I could not get `.fixed_size()`, `min_size()`, nor `.default_size()`
-                    // to work on the Window instance itself, so this is a workaround
-                    ui.set_min_size(egui::Vec2::new(150.0, 150.0));
-                    ui.set_max_size(egui::Vec2::new(150.0, 150.0));
+    /// Fades the panels behind the stickies to `Settings::window_opacity`, leaving the stickies
+    /// themselves as they are.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn apply_window_opacity(&self, ctx: &egui::Context) {
+        // Faded from the theme's fill each time, so the fade doesn't compound frame to frame
+        let theme = if ctx.style().visuals.dark_mode {
+            egui::Visuals::dark()
+        } else {
+            egui::Visuals::light()
+        };
+        let opacity = self.settings.window_opacity.clamp(MIN_WINDOW_OPACITY, 1.0);
+        let fill = theme.panel_fill.gamma_multiply(opacity);
+        if ctx.style().visuals.panel_fill != fill {
+            ctx.style_mut(|style| style.visuals.panel_fill = fill);
+        }
+    }
 
-                    let mut local_label = todo.label.clone();
+    /// Loads the background image picked in Settings, once per path, or frees it once it's
+    /// no longer picked.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_canvas_texture(&mut self, ctx: &egui::Context) {
+        let CanvasBackground::Image(path) = &self.settings.canvas_bg else {
+            self.canvas_texture = None;
+            return;
+        };
+        if self
+            .canvas_texture
+            .as_ref()
+            .is_some_and(|(loaded, _)| loaded == path)
+        {
+            return;
+        }
 
-                    ui.vertical(|ui| {
-                        ui.horizontal(|ui| {
-                            let id = "todo_actions";
-                            let container_width = ui.available_width();
+        let texture = match image::open(path) {
+            Ok(image) => {
+                let image = image.to_rgba8();
+                let size = [image.width() as usize, image.height() as usize];
+                let image = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+                Some(ctx.load_texture("canvas_background", image, Default::default()))
+            }
+            Err(err) => {
+                log::warn!("Failed to load background {}: {err}", path.display());
+                None
+            }
+        };
+        self.canvas_texture = Some((path.clone(), texture));
+    }
 
-                            let mut local_checked = todo.checked;
-                            if ui.checkbox(&mut local_checked, "").changed() {
-                                self.effects_tx.send(Effect::CheckTodo(index)).unwrap();
-                            }
+    /// Writes the screenshot asked for by `Effect::ExportImage`, if it came this frame, cropped
+    /// to the central panel. It can take a frame or two to arrive after it's asked for.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_screenshot(&mut self, ctx: &egui::Context) {
+        let screenshot = ctx.input(|i| {
+            i.events.iter().find_map(|event| match event {
+                egui::Event::Screenshot { image, .. } => Some(image.clone()),
+                _ => None,
+            })
+        });
+        let (Some(image), Some(path)) = (screenshot, &self.screenshot_path) else {
+            return;
+        };
 
-                            // We want to right justify the Edit and Delete buttons
-                            if let Some(stored_width) = self.calculated.get(id) {
-                                let offset = container_width - stored_width;
-                                ui.add_space(offset);
-                            }
+        // Once the panels are laid out, what's left over is the central panel
+        let board = image.region(&ctx.available_rect(), Some(ctx.pixels_per_point()));
+        let [width, height] = board.size.map(|side| side as u32);
+        let saved =
+            image::save_buffer(path, board.as_raw(), width, height, image::ColorType::Rgba8);
+        if let Err(err) = saved {
+            log::warn!("Failed to save image to {}: {err}", path.display());
+        }
+        self.screenshot_path = None;
+    }
 
-                            if todo.edit_mode {
-                                if ui.button("Save").clicked() {
-                                    self.effects_tx.send(Effect::EditTodo(index)).unwrap();
-                                    self.effects_tx
-                                        .send(Effect::SaveTodo(index, local_label.clone()))
-                                        .unwrap();
-                                }
-                            } else if ui.button("Edit").clicked() {
-                                self.effects_tx.send(Effect::EditTodo(index)).unwrap();
-                            }
+    /// Compiles the search query once it changes, rather than on every frame it's matched on.
+    fn compile_search(&mut self) {
+        self.search_pattern = self.search_regex.then(|| {
+            regex::RegexBuilder::new(&self.search_query)
+                .case_insensitive(true)
+                .build()
+        });
+    }
 
-                            if ui.button("Delete").clicked() {
-                                self.effects_tx.send(Effect::DeleteTodo(index)).unwrap();
-                            }
+    /// Whether the label matches the search, or the search is empty.
+    fn matches_search(&self, label: &str, query: &str) -> bool {
+        match &self.search_pattern {
+            Some(Ok(pattern)) => pattern.is_match(label),
+            // An invalid pattern matches nothing, rather than everything
+            Some(Err(_)) => false,
+            None => label.to_lowercase().contains(query),
+        }
+    }
 
-                            if self.calculated.get(id).is_none() {
-                                self.effects_tx
-                                    .send(Effect::InsertCalculated(
-                                        id.to_string(),
-                                        ui.min_rect().width(),
-                                    ))
-                                    .unwrap();
-                            }
-                        });
+    /// `text` in the language picked in Settings.
+    fn tr(&self, text: Text) -> &'static str {
+        text.translate(self.settings.language)
+    }
 
-                        let id = "todo_text";
-                        let container_height = ui.available_height();
-                        if let Some(stored_height) = self.calculated.get(id) {
-                            let offset = (container_height - stored_height) / 2.0;
-                            ui.add_space(offset);
-                        }
+    /// Queues an effect to be applied after this frame renders.
+    fn send(&self, effect: Effect) {
+        // The receiver lives alongside the sender in `self`, so this only fails mid-teardown
+        if self.effects_tx.send(effect).is_err() {
+            log::warn!("Dropped an effect because the effects channel is closed");
+        }
+    }
 
-                        ui.vertical_centered(|ui| {
-                            if todo.edit_mode {
-                                if ui.text_edit_singleline(&mut local_label).lost_focus()
-                                    && ui.input(|i| i.key_pressed(egui::Key::Enter))
-                                {
-                                    self.effects_tx.send(Effect::EditTodo(index)).unwrap();
-                                    self.effects_tx
-                                        .send(Effect::SaveTodo(index, local_label.clone()))
-                                        .unwrap();
-                                }
+    /// Applies the effects sent since the last call, in the order given by
+    /// `Effect::apply_order` rather than the order they were sent.
+    fn apply_effects(&mut self, ctx: &egui::Context) {
+        let mut effects: Vec<Effect> = self.effects_rx.try_iter().collect();
+        effects.sort_by_key(Effect::apply_order);
+        if effects.iter().any(Effect::changes_saved_state) {
+            self.unsaved = true;
+        }
 
-                                self.effects_tx
-                                    .send(Effect::SaveTodo(index, local_label.clone()))
-                                    .unwrap();
-                            } else {
-                                ui.add(egui::Label::new(&todo.label).wrap(true));
-                            }
-                        });
+        // Effects land after rendering, so show their result without waiting for more input
+        if !effects.is_empty() {
+            ctx.request_repaint();
+        }
+        for effect in effects {
+            self.apply_undoable(ctx, effect);
+        }
+    }
 
-                        if self.calculated.get(id).is_none() {
-                            self.effects_tx
-                                .send(Effect::InsertCalculated(
-                                    id.to_string(),
-                                    container_height - ui.available_height(),
-                                ))
-                                .unwrap();
-                        }
-                    });
-                });
-            }
+    /// How long until something on screen changes by itself: a relative timestamp ticking over,
+    /// or an alarm, reopening or archiving coming due. Animations request their own repaints.
+    fn next_wake(&self, now: chrono::DateTime<Utc>) -> Option<chrono::Duration> {
+        let labels = self
+            .page_todos()
+            .map(|(_, todo)| label_refresh_in(now - todo.created_at));
 
-            ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
-                ui.horizontal(|ui| {
-                    ui.spacing_mut().item_spacing.x = 0.0;
-                    ui.add(egui::github_link_file!(
-                        "https://github.com/pmillspaugh/stickies/blob/main/",
-                        "Source code. "
-                    ));
-                    ui.label("Powered by ");
-                    ui.hyperlink_to("egui", "https://github.com/emilk/egui");
-                    ui.label(" and ");
-                    ui.hyperlink_to(
-                        "eframe",
-                        "https://github.com/emilk/egui/tree/master/crates/eframe",
-                    );
-                    ui.label(".");
-                });
-                egui::warn_if_debug_build(ui);
+        let quiet = self
+            .settings
+            .quiet_hours
+            .is_some_and(|hours| in_quiet_hours(hours, self.settings.local(now).time()));
+        let events = self.core.todos.iter().flat_map(|todo| {
+            let archives_in = self
+                .settings
+                .auto_archive_after
+                .and_then(|delay| todo.archives_in(delay, now));
+            let alarm = todo.alarm.map(|alarm| {
+                // Alarms held for quiet hours are checked on again each minute
+                let until = alarm - now;
+                if quiet && until <= chrono::Duration::zero() {
+                    chrono::Duration::minutes(1)
+                } else {
+                    until
+                }
             });
+            let reopens_in = todo.reopens_at.map(|reopens_at| reopens_at - now);
+            let wakes_in = todo.hidden_until.map(|until| until - now);
+            [archives_in, alarm, reopens_in, wakes_in]
+                .into_iter()
+                .flatten()
         });
-    }
-}
 
-enum Effect {
-    DraftTodo(String),
-    AddTodo(String),
-    EditTodo(usize),
-    SaveTodo(usize, String),
-    CheckTodo(usize),
-    DeleteTodo(usize),
+        labels
+            .chain(events)
+            .map(|wake| wake.max(chrono::Duration::zero()))
+            .min()
+    }
 
-    InsertCalculated(String, f32),
-}
+    /// Applies `effect`, first saving an undo step if it's one the user can take back.
+    fn apply_undoable(&mut self, ctx: &egui::Context, effect: Effect) {
+        if !effect.is_undoable() {
+            self.apply_effect(ctx, effect);
+            return;
+        }
 
-#[derive(serde::Deserialize, serde::Serialize)]
-struct Todo {
-    id: egui::Id,
-    label: String,
-    checked: bool,
-    edit_mode: bool,
-}
+        let label_edit = match effect {
+            Effect::SaveTodo(index, _)
+            | Effect::SaveBack(index, _)
+            | Effect::SetRecurrence(index, _) => Some(index),
+            _ => None,
+        };
+        let before = UndoStep {
+            todos: self.core.todos.clone(),
+            boards: self.boards.clone(),
+            active_board: self.active_board,
+            label_edit,
+        };
+        self.apply_effect(ctx, effect);
+        if self.core.todos == before.todos && self.boards == before.boards {
+            return;
+        }
+        self.redo_stack.clear();
 
-impl Todo {
-    fn new(id: egui::Id, label: String) -> Self {
-        Self {
-            id,
-            label,
-            checked: false,
-            edit_mode: false,
+        // Typing into a sticky is one step, rather than one per keystroke
+        let last_edit = self.undo_stack.last().and_then(|step| step.label_edit);
+        if label_edit.is_none() || label_edit != last_edit {
+            if self.undo_stack.len() == UNDO_LIMIT {
+                self.undo_stack.remove(0);
+            }
+            self.undo_stack.push(before);
         }
     }
-}
 
-impl eframe::App for AppState {
-    /// Called by the frame work to save state before shutdown.
-    fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        eframe::set_value(storage, eframe::APP_KEY, self);
+    /// Puts back the todos and boards from `step`, returning the ones it replaced.
+    fn restore(&mut self, step: UndoStep) -> UndoStep {
+        UndoStep {
+            todos: std::mem::replace(&mut self.core.todos, step.todos),
+            boards: std::mem::replace(&mut self.boards, step.boards),
+            active_board: std::mem::replace(&mut self.active_board, step.active_board),
+            label_edit: None,
+        }
     }
 
-    /// Called each time the UI needs repainting, which may be many times per second.
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        self.render(ctx);
-        self.apply_effects(ctx);
+    fn apply_effect(&mut self, ctx: &egui::Context, effect: Effect) {
+        match effect {
+            Effect::DraftTodo(draft) => {
+                self.core.draft = draft;
+            }
+            Effect::Batch(effects) => {
+                // The whole batch shares the undo step taken by `apply_undoable`
+                for effect in effects {
+                    self.apply_effect(ctx, effect);
+                }
+            }
+            Effect::Undo => {
+                if let Some(step) = self.undo_stack.pop() {
+                    let undone = self.restore(step);
+                    self.redo_stack.push(undone);
+                }
+            }
+            Effect::Redo => {
+                if let Some(step) = self.redo_stack.pop() {
+                    let redone = self.restore(step);
+                    self.undo_stack.push(redone);
+                }
+            }
+            Effect::AddTodo(label) => {
+                let label = self.saved_label(label);
+                let id = next_todo_id(ctx);
+                let index = self.core.todos.len();
+                match self.label_alarm(&label) {
+                    Some((label, alarm)) => {
+                        self.core.add_todo(id, self.active_board, label);
+                        self.core.set_alarm(index, Some(alarm));
+                    }
+                    None => self.core.add_todo(id, self.active_board, label),
+                }
+                let board = self.boards.get(self.active_board);
+                if let Some(color) = board.and_then(|board| board.default_color) {
+                    self.core.set_color(index, Some(color));
+                }
+            }
+            Effect::ImportStickies(path) => self.import_stickies(ctx, &path),
+            Effect::ImportBoard(path) => self.import_board(ctx, &path),
+            Effect::ExportBoard(index, path) => {
+                let Some(board) = self.boards.get(index) else {
+                    return;
+                };
+                let stickies = self
+                    .core
+                    .todos
+                    .iter()
+                    .filter(|todo| todo.board == index)
+                    .map(|todo| BoardSticky {
+                        todo: todo.clone(),
+                        position: ctx
+                            .memory(|mem| mem.area_rect(todo.id))
+                            .map(|rect| rect.min),
+                    })
+                    .collect();
+                let file = BoardFile {
+                    board: board.clone(),
+                    stickies,
+                };
+                let written = serde_json::to_string_pretty(&file)
+                    .map_err(|err| err.to_string())
+                    .and_then(|text| std::fs::write(&path, text).map_err(|err| err.to_string()));
+                if let Err(err) = written {
+                    log::warn!("Failed to export to {}: {err}", path.display());
+                }
+            }
+            Effect::ExportImage(path) => {
+                self.screenshot_path = Some(path);
+                ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot);
+            }
+            Effect::ExportSingle(index, dir) => {
+                let Some(todo) = self.core.todos.get(index) else {
+                    return;
+                };
+                let path = offload_path(&dir, &todo.label);
+                let mut markdown =
+                    todos_to_markdown(std::slice::from_ref(todo), self.settings.export_width);
+                if !todo.back.is_empty() {
+                    markdown.push('\n');
+                    markdown.push_str(&todo.back);
+                    markdown.push('\n');
+                }
+                // Only taken off the board once it's safely written
+                match std::fs::write(&path, markdown) {
+                    Ok(()) => self.apply_effect(ctx, Effect::DeleteTodo(index)),
+                    Err(err) => {
+                        log::warn!("Failed to export to {}: {err}", path.display());
+                        self.export_error =
+                            Some(format!("Couldn't save to {}: {err}", path.display()));
+                    }
+                }
+            }
+            Effect::EditTodo(index) => {
+                // Tidied as editing ends rather than on each keystroke, which would trim away
+                // the space typed before the next word
+                if let Some(todo) = self.core.todos.get(index).filter(|todo| todo.edit_mode) {
+                    let label = self.saved_label(todo.label.clone());
+                    self.core.save_label(index, label);
+                }
+                self.core.toggle_edit(index);
+            }
+            Effect::SaveTodo(index, label) => {
+                self.core.save_label(index, label);
+                if let Some(todo) = self.core.todos.get(index) {
+                    if !self.unsaved_labels.contains(&todo.id) {
+                        self.unsaved_labels.push(todo.id);
+                    }
+                }
+            }
+            Effect::SaveBack(index, back) => self.core.save_back(index, back),
+            Effect::FlipTodo(index) => self.core.flip(index),
+            Effect::AddSubtask(index, parent) => {
+                self.core.add_subtask(index, parent);
+                self.new_subtask = self
+                    .core
+                    .todos
+                    .get(index)
+                    .filter(|todo| todo.edit_mode)
+                    .and_then(|todo| {
+                        let path = match parent {
+                            Some(node) => SubtaskPath::child(
+                                node,
+                                todo.subtasks.get(node)?.children.len() - 1,
+                            ),
+                            None => SubtaskPath::node(todo.subtasks.len().checked_sub(1)?),
+                        };
+                        Some((todo.id, path))
+                    });
+            }
+            Effect::SaveSubtask(index, path, label) => {
+                self.core.save_subtask(index, path, label);
+            }
+            Effect::CheckSubtask(index, path) => self.core.check_subtask(index, path),
+            Effect::CheckAllSubtasks(index) => self.core.check_all_subtasks(index),
+            Effect::DeleteSubtask(index, path) => self.core.delete_subtask(index, path),
+            Effect::ToggleSubtaskCollapsed(index, node_index) => {
+                self.core.toggle_subtask_collapsed(index, node_index);
+            }
+            Effect::DuplicateTodo(index) => {
+                let Some(todo) = self.core.todos.get(index) else {
+                    return;
+                };
+
+                // Open the copy just off the original, so both stay in view
+                let position = ctx
+                    .memory(|mem| mem.area_rect(todo.id))
+                    .map(|rect| rect.min + egui::vec2(20.0, 20.0));
+                self.core.duplicate_todo(index, next_todo_id(ctx), position);
+            }
+            Effect::ToggleArchived(index) => self.core.toggle_archived(index),
+            Effect::TogglePinned(index) => self.core.toggle_pinned(index),
+            Effect::ToggleHidden(index) => self.core.toggle_hidden(index),
+            Effect::ToggleRevealed(index) => self.core.toggle_revealed(index),
+            Effect::SetColor(index, color) => self.core.set_color(index, color),
+            Effect::MoveToBoard(index, board) => self.core.move_to_board(index, board),
+            Effect::ResizeTodo(index, size) => {
+                let size = locked_size(size, self.settings.aspect_ratio);
+                self.core.resize(index, size);
+            }
+            Effect::SetRecurrence(index, rule) => self.core.set_recurrence(index, rule),
+            Effect::SetEffort(index, effort) => self.core.set_effort(index, effort),
+            Effect::SetCodeLang(index, code_lang) => self.core.set_code_lang(index, code_lang),
+            Effect::LinkTodos(index, target) => self.core.link_todos(index, target),
+            Effect::UnlinkTodos(index, target) => self.core.unlink_todos(index, target),
+            Effect::SnapTodo(index, position) => self.core.snap_to(index, position),
+            Effect::FocusTodo(id) => self.focused_todo = id,
+            Effect::ScrollList(offset) => self.list_scroll = offset,
+            Effect::SaveNow => self.save_requested = true,
+            Effect::RaiseTodo(index) => self.core.raise(index),
+            Effect::SelectDetail(id) => self.selected_detail = id,
+            Effect::CheckTodo(index) => match self.settings.time_zone {
+                Some(zone) => self.core.check_todo(index, Utc::now().with_timezone(&zone)),
+                None => self.core.check_todo(index, Local::now()),
+            },
+            Effect::AttachFile(index, attachment) => self.core.attach(index, attachment),
+            Effect::PeelTodo(id) => {
+                if !self.peeling.contains(&id) {
+                    self.peeling.push(id);
+                }
+            }
+            Effect::SetAlarm(index, alarm) => self.core.set_alarm(index, alarm),
+            Effect::SetReminders(index, count, interval) => {
+                self.core.set_reminders(index, count, interval);
+            }
+            Effect::DismissReminders(index) => self.core.dismiss_reminders(index),
+            Effect::SnoozeTodo(index, until) => self.core.snooze(index, until),
+            Effect::PopTodo(id) => {
+                if !self.popping.contains(&id) {
+                    self.popping.push(id);
+                }
+                self.raise_todo(ctx, id);
+            }
+            Effect::EndPop(id) => self.popping.retain(|popping| *popping != id),
+            Effect::DeleteTodo(index) => {
+                // Forget the peel, so undoing the delete brings the sticky back
+                if let Some(todo) = self.core.todos.get(index) {
+                    let id = todo.id;
+                    self.peeling.retain(|peeling| *peeling != id);
+                }
+                self.core.delete_todo(index);
+            }
+            Effect::Reorder(from, to) => self.core.reorder(from, to),
+            Effect::MoveUp(index) => self.core.move_up(index),
+            Effect::MoveDown(index) => self.core.move_down(index),
+
+            Effect::AddZone => {
+                // Cascade new zones so they don't hide each other
+                let offset = 20.0 * self.zones.len() as f32;
+                let rect = egui::Rect::from_min_size(
+                    egui::pos2(40.0 + offset, 140.0 + offset),
+                    egui::vec2(320.0, 220.0),
+                );
+                self.zones.push(Zone::new(rect));
+            }
+            Effect::UpdateZone(index, zone) => {
+                if let Some(existing) = self.zones.get_mut(index) {
+                    *existing = zone;
+                }
+            }
+            Effect::DeleteZone(index) => {
+                if index < self.zones.len() {
+                    self.zones.remove(index);
+                }
+            }
+
+            Effect::SwitchBoard(index) => {
+                if index < self.boards.len() {
+                    self.active_board = index;
+                    self.page = 0;
+                }
+            }
+            Effect::PromoteToBoard(id, archive) => {
+                let Some(index) = self.core.todos.iter().position(|todo| todo.id == id) else {
+                    return;
+                };
+                let board = self.boards.len();
+                let todo = &self.core.todos[index];
+                let archived = todo.archived;
+                self.boards.push(Board::new(todo.label.clone()));
+                self.core
+                    .promote_subtasks(index, board, || next_todo_id(ctx));
+                if archive && !archived {
+                    self.core.toggle_archived(index);
+                }
+                self.active_board = board;
+            }
+            Effect::AddBoard => {
+                let name = format!("Board {}", self.boards.len() + 1);
+                self.boards.push(Board::new(name));
+                self.active_board = self.boards.len() - 1;
+            }
+            Effect::RenameBoard(index, name) => {
+                if let Some(board) = self.boards.get_mut(index) {
+                    board.name = name;
+                }
+            }
+            Effect::SetSortMode(index, sort_mode) => {
+                if let Some(board) = self.boards.get_mut(index) {
+                    board.sort_mode = sort_mode;
+                }
+            }
+            Effect::SetBoardColor(index, color) => {
+                if let Some(board) = self.boards.get_mut(index) {
+                    board.default_color = color;
+                }
+            }
+            Effect::SetBoardQuiet(index, quiet) => {
+                if let Some(board) = self.boards.get_mut(index) {
+                    board.quiet = quiet;
+                }
+            }
+            Effect::DuplicateBoard(index) => {
+                let Some(board) = self.boards.get(index) else {
+                    return;
+                };
+
+                let copy_index = self.boards.len();
+                self.boards.push(Board {
+                    sort_mode: board.sort_mode,
+                    default_color: board.default_color,
+                    quiet: board.quiet,
+                    ..Board::new(format!("{} (copy)", board.name))
+                });
+
+                self.core.duplicate_board(index, copy_index, |todo| {
+                    // Fresh ids keep egui's window state apart, so copy each position over
+                    let position = ctx.memory(|mem| mem.area_rect(todo.id)).map(|r| r.min);
+                    (next_todo_id(ctx), position)
+                });
+                self.active_board = copy_index;
+            }
+
+            Effect::ConfirmReset(confirming) => {
+                self.confirming_reset = confirming;
+            }
+            Effect::ConfirmCheck(id) => {
+                self.confirming_check = id;
+            }
+            Effect::ConfirmDeleteShown(confirming) => {
+                self.confirming_delete_shown = confirming;
+            }
+            Effect::DraftShownTag(draft) => {
+                self.shown_tag_draft = draft;
+            }
+            Effect::ResetAll => {
+                self.core.todos.clear();
+                self.undo_stack.clear();
+                self.redo_stack.clear();
+                self.zones.clear();
+                self.boards = vec![Board::new("My board".to_owned())];
+                self.active_board = 0;
+                self.calculated.clear();
+                self.core.draft.clear();
+                self.confirming_reset = false;
+            }
+
+            Effect::OpenSearch => {
+                self.search_open = true;
+                // The search box first renders next frame, which focus requests allow for
+                ctx.memory_mut(|mem| mem.request_focus(search_id()));
+            }
+            Effect::CloseSearch => {
+                self.search_open = false;
+                self.search_query.clear();
+                self.compile_search();
+            }
+            Effect::SearchTodos(query) => {
+                self.search_query = query;
+                self.page = 0;
+                self.compile_search();
+            }
+            Effect::ToggleSearchRegex => {
+                self.search_regex = !self.search_regex;
+                self.page = 0;
+                self.compile_search();
+            }
+            Effect::SetCompletionFilter(filter) => {
+                self.completion_filter = filter;
+                self.page = 0;
+            }
+            Effect::SetPage(page) => {
+                self.page = page;
+            }
+            Effect::ToggleReview => {
+                self.review_open = !self.review_open;
+            }
+            Effect::ToggleChrome => {
+                self.chrome_hidden = !self.chrome_hidden;
+            }
+            Effect::ToggleAgenda => {
+                self.agenda_open = !self.agenda_open;
+            }
+            Effect::SetFocusMode(id) => {
+                self.focus_mode = id;
+            }
+            Effect::OpenGoTo(open) => {
+                self.go_to_draft = open.then(String::new);
+                if open {
+                    ctx.memory_mut(|mem| mem.request_focus(go_to_id()));
+                }
+            }
+            Effect::DraftGoTo(draft) => {
+                self.go_to_draft = Some(draft);
+            }
+            Effect::GoTo(position) => {
+                let Some(id) = self.visible_todos().nth(position).map(|(_, todo)| todo.id) else {
+                    return;
+                };
+
+                self.focused_todo = Some(id);
+                self.raise_todo(ctx, id);
+                if let Some(page_size) = self.settings.page_size.filter(|&size| size > 0) {
+                    self.page = position / page_size;
+                }
+                self.go_to_draft = None;
+            }
+
+            Effect::DraftPassphrase(passphrase) => {
+                self.passphrase_draft = passphrase;
+            }
+            Effect::Unlock(passphrase) => {
+                let Some(encrypted) = &self.locked else {
+                    return;
+                };
+
+                let unlocked = encrypted
+                    .decrypt(&passphrase)
+                    .and_then(|(plaintext, cipher)| {
+                        let loaded = std::str::from_utf8(&plaintext)
+                            .map(AppState::parse_saved)
+                            .map_err(|_| encryption::Error::Corrupt)?;
+                        Ok((loaded, cipher))
+                    });
+                match unlocked {
+                    Ok((loaded, cipher)) => {
+                        self.replace_with(loaded);
+                        self.cipher = Some(cipher);
+                    }
+                    Err(err) => {
+                        self.encryption_error = Some(format!("Couldn't unlock: {err}"));
+                    }
+                }
+            }
+            Effect::DiscardLocked => {
+                self.replace_with(AppState::default());
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Effect::ResolveStorageChange(reload) => {
+                self.storage_changed = false;
+                let Some(storage) = &mut self.custom_storage else {
+                    return;
+                };
+                if !reload {
+                    storage.overwrite_changes();
+                    self.save_requested = true;
+                    return;
+                }
+                match storage.reload() {
+                    Ok(()) => {
+                        let loaded = Self::load(storage);
+                        self.replace_with(loaded);
+                    }
+                    Err(err) => {
+                        log::error!("Failed to reload {}: {err}", storage.path().display());
+                    }
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Effect::SetEmailError(err) => self.email_error = err,
+            Effect::SetExportError(err) => self.export_error = err,
+            Effect::OpenEncryptionDialog(open) => {
+                self.encryption_dialog_open = open;
+                self.passphrase_draft.clear();
+                self.encryption_error = None;
+            }
+            Effect::EnableEncryption(passphrase) => match Cipher::new(&passphrase) {
+                Ok(cipher) => {
+                    self.cipher = Some(cipher);
+                    self.encryption_dialog_open = false;
+                    self.passphrase_draft.clear();
+                    self.encryption_error = None;
+                }
+                Err(err) => {
+                    self.encryption_error = Some(format!("Couldn't encrypt: {err}"));
+                }
+            },
+            Effect::DisableEncryption => {
+                self.cipher = None;
+            }
+
+            Effect::DismissRecovery => {
+                if let Some(recovery) = &mut self.recovery {
+                    recovery.dismissed = true;
+                }
+            }
+
+            Effect::SetView(view) => {
+                self.view = view;
+            }
+            Effect::UpdateSettings(settings) => {
+                // Translated or compact buttons change width, so measure them again
+                if settings.language != self.settings.language
+                    || settings.compact != self.settings.compact
+                {
+                    self.calculated.clear();
+                }
+                self.settings = *settings;
+            }
+
+            Effect::InsertCalculated(name, value) => {
+                self.calculated.insert(name, value);
+                // self.calculated.clear();
+            }
+        }
+    }
+
+    /// Writes the state to `storage`, encrypted if encryption is on.
+    /// Returns whether the state was saved.
+    fn save_to(&self, storage: &mut dyn eframe::Storage) -> bool {
+        // Saving over unreadable state would lose it, so keep a copy alongside
+        if let Some(recovery) = &self.recovery {
+            storage.set_string(UNREADABLE_STATE_KEY, recovery.original.clone());
+        }
+
+        // Whichever key isn't in use is blanked, so startup knows how to load
+        let Some(cipher) = &self.cipher else {
+            eframe::set_value(storage, eframe::APP_KEY, self);
+            storage.set_string(encryption::ENCRYPTED_KEY, String::new());
+            return true;
+        };
+
+        let encrypted = ron::to_string(self)
+            .map_err(|_| encryption::Error::Encryption)
+            .and_then(|plaintext| cipher.encrypt(plaintext.as_bytes()));
+        match encrypted {
+            Ok(encrypted) => {
+                eframe::set_value(storage, encryption::ENCRYPTED_KEY, &encrypted);
+                storage.set_string(eframe::APP_KEY, String::new());
+                true
+            }
+            Err(err) => {
+                log::error!("Not saving, {err}");
+                false
+            }
+        }
+    }
+
+    /// Moves fields persisted in an older layout to where they live now.
+    fn migrate(mut self) -> Self {
+        if let Some(draft) = self.legacy_draft.take() {
+            self.core.draft = draft;
+        }
+        if let Some(todos) = self.legacy_todos.take() {
+            self.core.todos = todos;
+        }
+        self
+    }
+
+    /// Replaces the persisted state with `loaded`, keeping this state's effects channel and
+    /// where it's saved.
+    fn replace_with(&mut self, mut loaded: AppState) {
+        std::mem::swap(&mut loaded.effects_tx, &mut self.effects_tx);
+        std::mem::swap(&mut loaded.effects_rx, &mut self.effects_rx);
+        std::mem::swap(&mut loaded.launch_labels, &mut self.launch_labels);
+        #[cfg(not(target_arch = "wasm32"))]
+        std::mem::swap(&mut loaded.custom_storage, &mut self.custom_storage);
+        #[cfg(any(windows, target_os = "macos"))]
+        std::mem::swap(&mut loaded.tray, &mut self.tray);
+        *self = loaded;
+    }
+
+    /// Counts the stickies due today in the window's title, so the count shows in the taskbar.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn update_window_title(&mut self, ctx: &egui::Context) {
+        let title = match due_today(&self.core.todos, Utc::now(), &self.settings) {
+            0 => "stickies".to_owned(),
+            due => format!("stickies ({due} due)"),
+        };
+        if title != self.window_title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.window_title = title;
+        }
+    }
+
+    /// Shows the stickies due today beside the tray icon, and handles what's picked from its
+    /// menu.
+    #[cfg(any(windows, target_os = "macos"))]
+    fn update_tray(&mut self, ctx: &egui::Context) {
+        let Some(tray) = &mut self.tray else {
+            return;
+        };
+        tray.set_due(due_today(&self.core.todos, Utc::now(), &self.settings));
+        let Some(action) = tray.take_action() else {
+            return;
+        };
+        if let TrayAction::AddSticky = action {
+            ctx.memory_mut(|mem| mem.request_focus(draft_id()));
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+    }
+
+    /// Notices the storage file changing under the app, e.g. from another instance editing
+    /// the same file or a sync bringing in changes from another machine. Only the file picked
+    /// with `--storage` is watched, since eframe's own storage is only read on launch.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn watch_storage(&mut self) {
+        let Some(storage) = &self.custom_storage else {
+            return;
+        };
+        if storage.take_touched() && storage.changed_on_disk() {
+            self.storage_changed = true;
+        }
+    }
+
+    /// Emails the sticky whose alarm went off, with `Settings::email`. Sent from another thread,
+    /// since the server can take a while to answer, and any failure comes back as an effect.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn email_reminder(&self, ctx: &egui::Context, id: egui::Id) {
+        let Some(email) = self.settings.email.clone() else {
+            return;
+        };
+        let Some(todo) = self.core.todos.iter().find(|todo| todo.id == id) else {
+            return;
+        };
+        // Hidden labels stay out of the inbox too
+        let label = if todo.is_concealed() {
+            "A hidden sticky's alarm went off".to_owned()
+        } else {
+            todo.label.clone()
+        };
+        let effects_tx = self.effects_tx.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            if let Err(err) = email.send(&label) {
+                log::warn!("Failed to email a reminder: {err}");
+                effects_tx.send(Effect::SetEmailError(Some(err))).ok();
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    /// Says why a reminder email failed, off to the side so it doesn't get in the way.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn render_email_error(&self, ctx: &egui::Context, err: &str) {
+        egui::Window::new("Couldn't email a reminder")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-8.0, -8.0))
+            .show(ctx, |ui| {
+                ui.label(err);
+                ui.weak("Check the email reminder settings.");
+                if ui.button("OK").clicked() {
+                    self.send(Effect::SetEmailError(None));
+                }
+            });
+    }
+
+    /// Says why a sticky couldn't be exported, and so is still on the board.
+    fn render_export_error(&self, ctx: &egui::Context, err: &str) {
+        egui::Window::new("Couldn't export the sticky")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-8.0, -8.0))
+            .show(ctx, |ui| {
+                ui.label(err);
+                ui.weak("It's been left on the board.");
+                if ui.button("OK").clicked() {
+                    self.send(Effect::SetExportError(None));
+                }
+            });
+    }
+
+    /// Asks whether to load the storage file's changes on disk, or save over them.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn render_storage_changed(&self, ctx: &egui::Context) {
+        let Some(storage) = &self.custom_storage else {
+            return;
+        };
+
+        egui::Window::new("Stickies changed elsewhere")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} was changed outside this window, maybe by another copy of the app \
+                     or a sync. Saving now would lose those changes.",
+                    storage.path().display()
+                ));
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Reload")
+                        .on_hover_text("Load the changes, dropping what's changed here since")
+                        .clicked()
+                    {
+                        self.send(Effect::ResolveStorageChange(true));
+                    }
+                    if ui
+                        .button("Keep mine")
+                        .on_hover_text("Save what's here over the changes")
+                        .clicked()
+                    {
+                        self.send(Effect::ResolveStorageChange(false));
+                    }
+                });
+            });
+    }
+
+    fn render(&self, ctx: &egui::Context) {
+        if self.locked.is_some() {
+            self.render_unlock(ctx);
+            return;
+        }
+
+        // Tab adds a subtask to the focused sticky, rather than moving keyboard focus. It's
+        // checked before any widget is shown, since the first one would take focus from it
+        let add_subtask_focused = self.view == ViewMode::Canvas
+            && self.focused_todo.is_some()
+            && ctx.memory(|mem| mem.focused().is_none())
+            && ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Tab));
+
+        // These shortcuts are handled here rather than by the top bar's buttons, so they still
+        // work while it's hidden
+        self.handle_chrome_shortcuts(ctx);
+
+        let mut trash = None;
+        egui::TopBottomPanel::top("top_panel").show_animated(ctx, !self.chrome_hidden, |ui| {
+            egui::menu::bar(ui, |ui| {
+                egui::widgets::global_dark_light_mode_buttons(ui);
+
+                ui.separator();
+
+                self.render_board_menu(ui);
+
+                ui.separator();
+
+                let mut local_view = self.view;
+                ui.selectable_value(&mut local_view, ViewMode::Canvas, self.tr(Text::Canvas));
+                ui.selectable_value(&mut local_view, ViewMode::List, self.tr(Text::List));
+                ui.selectable_value(&mut local_view, ViewMode::Detail, self.tr(Text::Detail));
+                if local_view != self.view {
+                    self.send(Effect::SetView(local_view));
+                }
+                if ui
+                    .selectable_label(self.review_open, self.tr(Text::Review))
+                    .on_hover_text("What was added and completed over the last week")
+                    .clicked()
+                {
+                    self.send(Effect::ToggleReview);
+                }
+                if ui
+                    .selectable_label(self.agenda_open, self.tr(Text::Agenda))
+                    .on_hover_text("Stickies with alarms set, soonest first")
+                    .clicked()
+                {
+                    self.send(Effect::ToggleAgenda);
+                }
+                let shortcut = ui.ctx().format_shortcut(&CHROME_SHORTCUT);
+                if ui
+                    .button("⛶")
+                    .on_hover_text(format!(
+                        "Hide the top and status bars ({shortcut}). {shortcut} or Escape \
+                         brings them back"
+                    ))
+                    .clicked()
+                {
+                    self.send(Effect::ToggleChrome);
+                }
+
+                ui.separator();
+
+                let mut local_filter = self.completion_filter;
+                ui.selectable_value(&mut local_filter, CompletionFilter::All, self.tr(Text::All));
+                ui.selectable_value(
+                    &mut local_filter,
+                    CompletionFilter::Active,
+                    self.tr(Text::Active),
+                );
+                ui.selectable_value(
+                    &mut local_filter,
+                    CompletionFilter::Completed,
+                    self.tr(Text::Completed),
+                );
+                ui.selectable_value(
+                    &mut local_filter,
+                    CompletionFilter::Archived,
+                    self.tr(Text::Archived),
+                );
+                if local_filter != self.completion_filter {
+                    self.send(Effect::SetCompletionFilter(local_filter));
+                }
+                self.render_tags_menu(ui);
+                self.render_snoozed_menu(ui);
+
+                let completed: Vec<usize> = self
+                    .visible_todos()
+                    .filter(|(_, todo)| todo.checked)
+                    .map(|(index, _)| index)
+                    .collect();
+                if ui
+                    .add_enabled(
+                        !completed.is_empty(),
+                        egui::Button::new(self.tr(Text::ClearCompleted)),
+                    )
+                    .on_hover_text("Delete the completed stickies shown")
+                    .clicked()
+                {
+                    self.send(delete_batch(completed));
+                }
+                if self.is_filtering() {
+                    self.render_check_shown(ui);
+                    self.render_shown_actions(ui);
+                }
+
+                if self.view == ViewMode::Canvas {
+                    trash = Some(self.render_trash(ui));
+                }
+
+                self.render_pagination(ui);
+
+                ui.separator();
+
+                if ui
+                    .add_enabled(
+                        !self.undo_stack.is_empty(),
+                        egui::Button::new(self.tr(Text::Undo)),
+                    )
+                    .on_hover_text(history_hover(ui, &UNDO_SHORTCUT, self.undo_stack.len()))
+                    .on_disabled_hover_text("Nothing to undo")
+                    .clicked()
+                {
+                    self.send(Effect::Undo);
+                }
+                if ui
+                    .add_enabled(
+                        !self.redo_stack.is_empty(),
+                        egui::Button::new(self.tr(Text::Redo)),
+                    )
+                    .on_hover_text(history_hover(ui, &REDO_SHORTCUT, self.redo_stack.len()))
+                    .on_disabled_hover_text("Nothing to redo")
+                    .clicked()
+                {
+                    self.send(Effect::Redo);
+                }
+
+                ui.separator();
+
+                if ui
+                    .add_enabled(
+                        self.view == ViewMode::Canvas,
+                        egui::Button::new(self.tr(Text::AddZone)),
+                    )
+                    .on_disabled_hover_text("Zones are only shown in the canvas view")
+                    .clicked()
+                {
+                    self.send(Effect::AddZone);
+                }
+
+                ui.separator();
+
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui
+                    .button(self.tr(Text::Import))
+                    .on_hover_text("Add notes exported as text from another Stickies app")
+                    .clicked()
+                {
+                    let path = rfd::FileDialog::new()
+                        .add_filter("Text", &["txt"])
+                        .pick_file();
+                    if let Some(path) = path {
+                        self.send(Effect::ImportStickies(path));
+                    }
+                }
+
+                ui.menu_button(self.tr(Text::Export), |ui| {
+                    if ui.button("Copy as Markdown").clicked() {
+                        let markdown =
+                            todos_to_markdown(&self.core.todos, self.settings.export_width);
+                        ui.output_mut(|o| o.copied_text = markdown);
+                        ui.close_menu();
+                    }
+
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui.button("Save as Markdown…").clicked() {
+                        ui.close_menu();
+                        let path = rfd::FileDialog::new()
+                            .add_filter("Markdown", &["md"])
+                            .set_file_name("stickies.md")
+                            .save_file();
+                        if let Some(path) = path {
+                            let markdown =
+                                todos_to_markdown(&self.core.todos, self.settings.export_width);
+                            if let Err(err) = std::fs::write(&path, markdown) {
+                                log::warn!("Failed to export to {}: {err}", path.display());
+                            }
+                        }
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui
+                        .button("Save as image…")
+                        .on_hover_text("A PNG of the board as it's shown")
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        let path = rfd::FileDialog::new()
+                            .add_filter("PNG", &["png"])
+                            .set_file_name("stickies.png")
+                            .save_file();
+                        if let Some(path) = path {
+                            self.send(Effect::ExportImage(path));
+                        }
+                    }
+                });
+
+                ui.menu_button(self.tr(Text::Settings), |ui| {
+                    let mut local_settings = self.settings.clone();
+                    ui.checkbox(
+                        &mut local_settings.celebrate_completion,
+                        "Celebrate completed stickies",
+                    );
+                    ui.checkbox(
+                        &mut local_settings.peel_on_delete,
+                        "Peel away deleted stickies",
+                    );
+                    ui.checkbox(
+                        &mut local_settings.click_to_check,
+                        "Check off stickies by clicking them",
+                    );
+                    ui.checkbox(&mut local_settings.fade_idle, "Fade stickies until hovered");
+                    let add_shortcut = ui.ctx().format_shortcut(&ADD_SHORTCUT);
+                    ui.checkbox(
+                        &mut local_settings.add_with_shortcut,
+                        format!("Add stickies with {add_shortcut} instead of Enter"),
+                    );
+                    ui.checkbox(
+                        &mut local_settings.actions_on_hover,
+                        "Only show sticky buttons on hover",
+                    );
+                    ui.checkbox(
+                        &mut local_settings.alarms_from_labels,
+                        "Set alarms from dates ending new stickies, like \"tomorrow 3pm\"",
+                    );
+                    ui.checkbox(
+                        &mut local_settings.remember_filters,
+                        "Remember search and filters on restart",
+                    );
+                    ui.checkbox(
+                        &mut local_settings.tidy_labels,
+                        "Trim labels and start them with a capital on save",
+                    );
+                    ui.checkbox(&mut local_settings.spellcheck, "Underline misspelled words")
+                        .on_hover_text("Checked against a bundled English word list");
+                    ui.checkbox(&mut local_settings.compact, "Compact stickies");
+                    ui.checkbox(
+                        &mut local_settings.number_stickies,
+                        "Number stickies in order, for Go to",
+                    );
+                    ui.horizontal(|ui| {
+                        ui.label("Background: ");
+                        let bg = &mut local_settings.canvas_bg;
+                        if ui
+                            .selectable_label(*bg == CanvasBackground::Theme, "Theme")
+                            .clicked()
+                        {
+                            *bg = CanvasBackground::Theme;
+                        }
+                        let is_color = matches!(bg, CanvasBackground::Color(_));
+                        if ui.selectable_label(is_color, "Color").clicked() && !is_color {
+                            *bg = CanvasBackground::Color(ui.visuals().faint_bg_color);
+                        }
+                        if let CanvasBackground::Color(color) = bg {
+                            ui.color_edit_button_srgba(color);
+                        }
+                        #[cfg(not(target_arch = "wasm32"))]
+                        {
+                            let image = match bg {
+                                CanvasBackground::Image(path) => Some(path.display().to_string()),
+                                _ => None,
+                            };
+                            let button = ui.selectable_label(image.is_some(), "Image…");
+                            if button
+                                .on_hover_text(image.unwrap_or_else(|| "Pick a PNG".to_owned()))
+                                .clicked()
+                            {
+                                if let Some(path) = rfd::FileDialog::new()
+                                    .add_filter("PNG", &["png"])
+                                    .pick_file()
+                                {
+                                    *bg = CanvasBackground::Image(path);
+                                }
+                            }
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(self.tr(Text::Language));
+                        egui::ComboBox::from_id_source("language")
+                            .selected_text(local_settings.language.name())
+                            .show_ui(ui, |ui| {
+                                for lang in Lang::ALL {
+                                    ui.selectable_value(
+                                        &mut local_settings.language,
+                                        lang,
+                                        lang.name(),
+                                    );
+                                }
+                            });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Sticky corners: ");
+                        ui.add(
+                            egui::Slider::new(&mut local_settings.sticky_rounding, 0.0..=20.0)
+                                .show_value(false),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Sticky shape: ");
+                        for (name, ratio) in ASPECT_RATIOS {
+                            ui.selectable_value(&mut local_settings.aspect_ratio, ratio, name)
+                                .on_hover_text("Kept when a sticky is resized");
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Sticky shadows: ");
+                        ui.add(
+                            egui::Slider::new(&mut local_settings.sticky_shadow, 0.0..=1.0)
+                                .show_value(false),
+                        );
+                    });
+                    #[cfg(not(target_arch = "wasm32"))]
+                    ui.horizontal(|ui| {
+                        ui.label("Window opacity: ");
+                        ui.add(
+                            egui::Slider::new(
+                                &mut local_settings.window_opacity,
+                                MIN_WINDOW_OPACITY..=1.0,
+                            )
+                            .show_value(false),
+                        )
+                        .on_hover_text("Shows the desktop through the window, where supported");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Export line width: ");
+                        ui.add(
+                            egui::DragValue::new(&mut local_settings.export_width)
+                                .clamp_range(MIN_EXPORT_WIDTH..=200),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Warn about labels longer than: ");
+                        ui.add(
+                            egui::DragValue::new(&mut local_settings.label_warn_chars)
+                                .clamp_range(10..=MAX_LABEL_CHARS)
+                                .suffix(" characters"),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Add bar: ");
+                        ui.selectable_value(
+                            &mut local_settings.add_bar,
+                            AddBarPlacement::Top,
+                            "Top",
+                        );
+                        ui.selectable_value(
+                            &mut local_settings.add_bar,
+                            AddBarPlacement::Bottom,
+                            "Bottom",
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Checkbox: ");
+                        ui.selectable_value(
+                            &mut local_settings.checkbox_placement,
+                            CheckboxPlacement::ActionRow,
+                            "In action row",
+                        );
+                        ui.selectable_value(
+                            &mut local_settings.checkbox_placement,
+                            CheckboxPlacement::WithLabel,
+                            "With label",
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Long labels: ");
+                        for overflow in LabelOverflow::ALL {
+                            ui.selectable_value(
+                                &mut local_settings.label_overflow,
+                                overflow,
+                                overflow.name(),
+                            );
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Stickies per page: ");
+                        egui::ComboBox::from_id_source("page_size")
+                            .selected_text(page_size_label(local_settings.page_size))
+                            .show_ui(ui, |ui| {
+                                for page_size in PAGE_SIZES {
+                                    ui.selectable_value(
+                                        &mut local_settings.page_size,
+                                        page_size,
+                                        page_size_label(page_size),
+                                    );
+                                }
+                            });
+                    });
+                    ui.horizontal(|ui| {
+                        let mut quiet = local_settings.quiet_hours.is_some();
+                        ui.checkbox(&mut quiet, "Quiet hours for alarms");
+                        if !quiet {
+                            local_settings.quiet_hours = None;
+                            return;
+                        }
+
+                        let (start, end) = local_settings
+                            .quiet_hours
+                            .unwrap_or_else(default_quiet_hours);
+                        let mut start_hour = start.hour();
+                        let mut end_hour = end.hour();
+                        ui.add(
+                            egui::DragValue::new(&mut start_hour)
+                                .clamp_range(0..=23)
+                                .suffix(":00"),
+                        );
+                        ui.label("to");
+                        ui.add(
+                            egui::DragValue::new(&mut end_hour)
+                                .clamp_range(0..=23)
+                                .suffix(":00"),
+                        );
+                        local_settings.quiet_hours = NaiveTime::from_hms_opt(start_hour, 0, 0)
+                            .zip(NaiveTime::from_hms_opt(end_hour, 0, 0));
+                    });
+                    #[cfg(not(target_arch = "wasm32"))]
+                    ui.menu_button("Email reminders", |ui| {
+                        let mut enabled = local_settings.email.is_some();
+                        ui.checkbox(&mut enabled, "Email stickies as their alarms go off");
+                        if !enabled {
+                            local_settings.email = None;
+                            return;
+                        }
+
+                        let email = local_settings
+                            .email
+                            .get_or_insert_with(EmailSettings::default);
+                        egui::Grid::new("email_settings")
+                            .num_columns(2)
+                            .show(ui, |ui| {
+                                ui.label("Send to: ");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut email.address)
+                                        .hint_text("you@example.com"),
+                                );
+                                ui.end_row();
+                                ui.label("SMTP server: ");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut email.server)
+                                        .hint_text("smtp.example.com"),
+                                );
+                                ui.end_row();
+                                ui.label("Username: ");
+                                ui.text_edit_singleline(&mut email.username);
+                                ui.end_row();
+                                ui.label("Password: ");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut email.password).password(true),
+                                );
+                                ui.end_row();
+                            });
+                        ui.weak(
+                            "Sent over STARTTLS on port 587. The password is saved with the \
+                             settings, so it's only encrypted while saved data is.",
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Open on: ");
+                        let board_name = |board: Option<usize>| match board {
+                            Some(index) => self
+                                .boards
+                                .get(index)
+                                .map_or("First board", |board| board.name.as_str()),
+                            None => "Last used board",
+                        };
+                        egui::ComboBox::from_id_source("startup_board")
+                            .selected_text(board_name(local_settings.startup_board))
+                            .show_ui(ui, |ui| {
+                                let boards =
+                                    std::iter::once(None).chain((0..self.boards.len()).map(Some));
+                                for board in boards {
+                                    ui.selectable_value(
+                                        &mut local_settings.startup_board,
+                                        board,
+                                        board_name(board),
+                                    );
+                                }
+                            });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Time zone: ");
+                        egui::ComboBox::from_id_source("time_zone")
+                            .selected_text(time_zone_label(local_settings.time_zone))
+                            .show_ui(ui, |ui| {
+                                let zones = chrono_tz::TZ_VARIANTS.into_iter().map(Some);
+                                for zone in std::iter::once(None).chain(zones) {
+                                    ui.selectable_value(
+                                        &mut local_settings.time_zone,
+                                        zone,
+                                        time_zone_label(zone),
+                                    );
+                                }
+                            });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Archive completed after: ");
+                        egui::ComboBox::from_id_source("auto_archive_after")
+                            .selected_text(archive_delay_label(local_settings.auto_archive_after))
+                            .show_ui(ui, |ui| {
+                                for delay in AUTO_ARCHIVE_DELAYS {
+                                    ui.selectable_value(
+                                        &mut local_settings.auto_archive_after,
+                                        delay,
+                                        archive_delay_label(delay),
+                                    );
+                                }
+                            });
+                    });
+
+                    if local_settings != self.settings {
+                        self.send(Effect::UpdateSettings(Box::new(local_settings)));
+                    }
+
+                    ui.separator();
+
+                    if self.cipher.is_some() {
+                        if ui.button("Stop encrypting saved data").clicked() {
+                            self.send(Effect::DisableEncryption);
+                            ui.close_menu();
+                        }
+                    } else if ui.button("Encrypt saved data…").clicked() {
+                        self.send(Effect::OpenEncryptionDialog(true));
+                        ui.close_menu();
+                    }
+
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some(storage) = &self.custom_storage {
+                        ui.weak(format!("Saving to {}", storage.path().display()));
+                    }
+
+                    if ui.button("Reset all…").clicked() {
+                        self.send(Effect::ConfirmReset(true));
+                        ui.close_menu();
+                    }
+                });
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    self.render_save_status(ui);
+                    ui.separator();
+                    self.render_search(ui);
+                });
+            });
+        });
+
+        if self.encryption_dialog_open {
+            self.render_encryption_dialog(ctx);
+        }
+
+        if ctx.input_mut(|i| i.consume_shortcut(&GO_TO_SHORTCUT)) {
+            self.send(Effect::OpenGoTo(self.go_to_draft.is_none()));
+        }
+        if let Some(draft) = &self.go_to_draft {
+            self.render_go_to(ctx, draft);
+        }
+
+        if let Some(recovery) = self.recovery.as_ref().filter(|r| !r.dismissed) {
+            self.render_recovery_notice(ctx, recovery);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.storage_changed {
+            self.render_storage_changed(ctx);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(err) = &self.email_error {
+            self.render_email_error(ctx, err);
+        }
+        if let Some(err) = &self.export_error {
+            self.render_export_error(ctx, err);
+        }
+
+        if self.confirming_reset {
+            egui::Window::new("Reset all?")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label("This deletes every sticky and zone. It can't be undone.");
+                    ui.horizontal(|ui| {
+                        if ui.button("Reset").clicked() {
+                            self.send(Effect::ResetAll);
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.send(Effect::ConfirmReset(false));
+                        }
+                    });
+                });
+        }
+
+        if self.confirming_delete_shown {
+            let shown: Vec<(usize, &Todo)> = self.visible_todos().collect();
+            egui::Window::new(format!("Delete {} stickies?", shown.len()))
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label("This deletes every sticky the filters show. Undo brings them back.");
+                    ui.horizontal(|ui| {
+                        if ui.button(self.tr(Text::Delete)).clicked() {
+                            self.send(delete_all(&shown));
+                            self.send(Effect::ConfirmDeleteShown(false));
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.send(Effect::ConfirmDeleteShown(false));
+                        }
+                    });
+                });
+        }
+
+        if let Some(id) = self.focus_mode {
+            self.render_focus_mode(ctx, id);
+        }
+        // Focus mode covers windows, so it asks in its own card instead
+        if let Some(id) = self.confirming_check.filter(|_| self.focus_mode.is_none()) {
+            self.render_confirm_check(ctx, id);
+        }
+
+        egui::TopBottomPanel::bottom("status_bar").show_animated(ctx, !self.chrome_hidden, |ui| {
+            self.render_status_bar(ui);
+        });
+        // Added after the status bar, so it sits just above it like a chat's message box
+        if self.settings.add_bar == AddBarPlacement::Bottom {
+            egui::TopBottomPanel::bottom("add_bar").show(ctx, |ui| {
+                ui.add_space(4.0);
+                self.render_add_bar(ui);
+                ui.add_space(4.0);
+            });
+        }
+
+        if self.review_open {
+            egui::SidePanel::right("review").show(ctx, |ui| {
+                self.render_review(ui);
+            });
+        }
+        if self.agenda_open {
+            egui::SidePanel::right("agenda").show(ctx, |ui| {
+                self.render_agenda(ui);
+            });
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            self.paint_canvas_background(ui);
+
+            ui.vertical_centered(|ui| {
+                ui.heading(self.tr(Text::Heading));
+            });
+
+            ui.add_space(10.0);
+
+            if self.settings.add_bar == AddBarPlacement::Top {
+                self.render_add_bar(ui);
+            }
+
+            ui.add_space(10.0);
+
+            if self.is_filtering() && self.visible_todos().next().is_none() {
+                ui.vertical_centered(|ui| {
+                    ui.weak(self.tr(Text::NoMatches));
+                });
+            }
+
+            match self.view {
+                ViewMode::Canvas => self.render_canvas(ui, trash, add_subtask_focused),
+                ViewMode::List => self.render_list(ui),
+                ViewMode::Detail => self.render_detail(ui),
+            }
+
+            ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
+                ui.horizontal(|ui| {
+                    ui.spacing_mut().item_spacing.x = 0.0;
+                    ui.add(egui::github_link_file!(
+                        "https://github.com/pmillspaugh/stickies/blob/main/",
+                        "Source code. "
+                    ));
+                    ui.label("Powered by ");
+                    ui.hyperlink_to("egui", "https://github.com/emilk/egui");
+                    ui.label(" and ");
+                    ui.hyperlink_to(
+                        "eframe",
+                        "https://github.com/emilk/egui/tree/master/crates/eframe",
+                    );
+                    ui.label(".");
+                });
+                egui::warn_if_debug_build(ui);
+            });
+        });
+    }
+
+    /// The "Add a sticky" row, centered, with a note on any alarm the draft's label sets.
+    fn render_add_bar(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            // Center the elements using the stored width from the previous frame
+            // TODO: to prevent flicker, the first frame should only calculate size and not actually render
+            // Can use Juan's helper: https://gist.github.com/juancampa/faf3525beefa477babdad237f5e81ffe
+            let id = "draft_todo";
+            if let Some(stored_width) = self.calculated.get(id) {
+                let offset = (ui.available_width() - stored_width) / 2.0;
+                ui.add_space(offset);
+            }
+
+            ui.label(self.tr(Text::AddSticky));
+
+            // TODO: wrap immutable reference in a Cow instead of creating local copy explicitly
+            let mut local_draft = self.core.draft.clone();
+            let mut draft_edit = egui::TextEdit::singleline(&mut local_draft).id(draft_id());
+            if self.settings.add_with_shortcut {
+                // Plain Enter then leaves the box alone, rather than ending the edit
+                draft_edit = draft_edit.return_key(ADD_SHORTCUT);
+            }
+            let mut draft_edit = ui.add(draft_edit);
+            if self.settings.add_with_shortcut {
+                let shortcut = ui.ctx().format_shortcut(&ADD_SHORTCUT);
+                draft_edit = draft_edit.on_hover_text(format!("Press {shortcut} to add"));
+            }
+            if draft_edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                self.send(Effect::AddTodo(local_draft.clone()));
+                local_draft.clear();
+            }
+
+            let can_drag = self.view == ViewMode::Canvas && !local_draft.trim().is_empty();
+            ui.add_enabled_ui(can_drag, |ui| {
+                let payload = DraftDrag(local_draft.clone());
+                ui.dnd_drag_source(ui.id().with("draft_drag"), payload, |ui| {
+                    ui.label("✋");
+                })
+                .response
+                .on_hover_text("Drag onto the canvas to add the sticky there")
+                .on_disabled_hover_text("Type a sticky, then drag it onto the canvas");
+            });
+
+            if ui.button(self.tr(Text::Save)).clicked() {
+                self.send(Effect::AddTodo(local_draft.clone()));
+                local_draft.clear();
+            }
+
+            ui.menu_button("From template", |ui| {
+                for template in &TEMPLATES {
+                    if ui.button(template.name).clicked() {
+                        let now = self.settings.local(Utc::now()).naive_local();
+                        let mut effects = template.effects(self.core.todos.len(), now);
+                        // Adding todos clears the draft, so put back anything being typed
+                        effects.push(Effect::DraftTodo(self.core.draft.clone()));
+                        self.send(Effect::Batch(effects));
+                        ui.close_menu();
+                    }
+                }
+            });
+
+            // Only sent on a change, so idle frames queue no effects and don't repaint again
+            if local_draft != self.core.draft {
+                self.send(Effect::DraftTodo(local_draft));
+            }
+
+            // Store the width for the next frame if this is the first frame
+            if self.calculated.get(id).is_none() {
+                self.send(Effect::InsertCalculated(
+                    id.to_string(),
+                    ui.min_rect().width(),
+                ));
+            }
+        });
+
+        if let Some((label, alarm)) = self.label_alarm(&self.core.draft) {
+            let alarm = self.settings.local(alarm).format("%a %b %-d %H:%M");
+            ui.weak(format!("\"{label}\" with an alarm on {alarm}"));
+        }
+    }
+
+    /// Switches between boards, and adds, renames, or duplicates them.
+    fn render_board_menu(&self, ui: &mut egui::Ui) {
+        let Some(active) = self.boards.get(self.active_board) else {
+            return;
+        };
+
+        ui.menu_button(format!("Board: {}", active.name), |ui| {
+            for (index, board) in self.boards.iter().enumerate() {
+                if ui
+                    .selectable_label(index == self.active_board, &board.name)
+                    .clicked()
+                {
+                    self.send(Effect::SwitchBoard(index));
+                    ui.close_menu();
+                }
+            }
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.label("Name: ");
+                let mut local_name = active.name.clone();
+                if ui.text_edit_singleline(&mut local_name).changed() {
+                    self.send(Effect::RenameBoard(self.active_board, local_name));
+                }
+            });
+            ui.menu_button("New sticky color", |ui| {
+                self.render_board_color(ui, active);
+            });
+            let mut local_celebrate = !active.quiet;
+            if ui
+                .add_enabled(
+                    self.settings.celebrate_completion,
+                    egui::Checkbox::new(&mut local_celebrate, "Celebrate completed stickies"),
+                )
+                .on_disabled_hover_text("Turned off for every board in Settings")
+                .changed()
+            {
+                self.send(Effect::SetBoardQuiet(self.active_board, !local_celebrate));
+            }
+            if ui.button("New board").clicked() {
+                self.send(Effect::AddBoard);
+                ui.close_menu();
+            }
+            if ui
+                .button("Duplicate board")
+                .on_hover_text("Copies every sticky into a new board, e.g. for a weekly list")
+                .clicked()
+            {
+                self.send(Effect::DuplicateBoard(self.active_board));
+                ui.close_menu();
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                ui.separator();
+                if ui
+                    .button("Export board…")
+                    .on_hover_text("Saves this board alone, to share or open on another machine")
+                    .clicked()
+                {
+                    ui.close_menu();
+                    let path = rfd::FileDialog::new()
+                        .add_filter("Board", &["json"])
+                        .set_file_name(format!("{}.json", active.name))
+                        .save_file();
+                    if let Some(path) = path {
+                        self.send(Effect::ExportBoard(self.active_board, path));
+                    }
+                }
+                if ui.button("Import board…").clicked() {
+                    ui.close_menu();
+                    let path = rfd::FileDialog::new()
+                        .add_filter("Board", &["json"])
+                        .pick_file();
+                    if let Some(path) = path {
+                        self.send(Effect::ImportBoard(path));
+                    }
+                }
+            }
+        });
+    }
+
+    /// Picks the color new stickies on the active board start with. Stickies already on it
+    /// keep theirs, unless recolored from here too.
+    fn render_board_color(&self, ui: &mut egui::Ui, board: &Board) {
+        color_swatch_row(ui, board.default_color, |_, color| {
+            self.send(Effect::SetBoardColor(self.active_board, Some(color)));
+        });
+        if ui.button("Default").clicked() {
+            self.send(Effect::SetBoardColor(self.active_board, None));
+        }
+
+        ui.separator();
+        if ui
+            .button("Recolor this board's stickies")
+            .on_hover_text("Gives every sticky on the board this color, as one undo step")
+            .clicked()
+        {
+            let effects = self
+                .core
+                .todos
+                .iter()
+                .enumerate()
+                .filter(|(_, todo)| todo.board == self.active_board)
+                .map(|(index, _)| Effect::SetColor(index, board.default_color))
+                .collect();
+            self.send(Effect::Batch(effects));
+            ui.close_menu();
+        }
+    }
+
+    /// Tells the user their saved state only partly loaded, and where the original went.
+    fn render_recovery_notice(&self, ctx: &egui::Context, recovery: &Recovery) {
+        egui::Window::new("Some saved data couldn't be read")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(
+                    "Your saved stickies didn't fully load, possibly because they were saved \
+                     by a different version of the app.",
+                );
+                ui.label(format!("Recovered {} stickies.", recovery.recovered_todos));
+
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(path) = &recovery.backup_path {
+                    ui.label(format!("The original was backed up to {}", path.display()));
+                }
+
+                if ui.button("OK").clicked() {
+                    self.send(Effect::DismissRecovery);
+                }
+            });
+    }
+
+    /// The passphrase prompt shown on startup when the saved state is encrypted.
+    fn render_unlock(&self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading(self.tr(Text::Heading));
+            });
+        });
+
+        egui::Window::new("Unlock stickies")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("Your stickies are encrypted. Enter your passphrase to load them.");
+
+                let mut local_passphrase = self.passphrase_draft.clone();
+                let response =
+                    ui.add(egui::TextEdit::singleline(&mut local_passphrase).password(true));
+                response.request_focus();
+                let submitted =
+                    response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+                if let Some(error) = &self.encryption_error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button("Unlock").clicked() || submitted {
+                        self.send(Effect::Unlock(local_passphrase.clone()));
+                    }
+                    if ui
+                        .button("Start over")
+                        .on_hover_text("Permanently discards the encrypted stickies")
+                        .clicked()
+                    {
+                        self.send(Effect::DiscardLocked);
+                    }
+                });
+
+                if local_passphrase != self.passphrase_draft {
+                    self.send(Effect::DraftPassphrase(local_passphrase));
+                }
+            });
+    }
+
+    /// Prompts for a passphrase to start encrypting the saved state with.
+    fn render_encryption_dialog(&self, ctx: &egui::Context) {
+        egui::Window::new("Encrypt saved stickies")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("Choose a passphrase. It can't be recovered if you forget it.");
+
+                let mut local_passphrase = self.passphrase_draft.clone();
+                ui.add(egui::TextEdit::singleline(&mut local_passphrase).password(true));
+
+                if let Some(error) = &self.encryption_error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(!local_passphrase.is_empty(), egui::Button::new("Encrypt"))
+                        .clicked()
+                    {
+                        self.send(Effect::EnableEncryption(local_passphrase.clone()));
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.send(Effect::OpenEncryptionDialog(false));
+                    }
+                });
+
+                if local_passphrase != self.passphrase_draft {
+                    self.send(Effect::DraftPassphrase(local_passphrase));
+                }
+            });
+    }
+
+    /// Asks for a sticky's number, counting the board's visible stickies in order from 1.
+    fn render_go_to(&self, ctx: &egui::Context, draft: &str) {
+        let count = self.visible_todos().count();
+        let target = draft
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|number| (1..=count).contains(number));
+
+        egui::Window::new("Go to sticky")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 40.0))
+            .show(ctx, |ui| {
+                let mut local_draft = draft.to_owned();
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut local_draft)
+                        .id(go_to_id())
+                        .hint_text(format!("1–{count}")),
+                );
+
+                if !draft.trim().is_empty() && target.is_none() {
+                    let error = if count == 0 {
+                        "No stickies are shown".to_owned()
+                    } else {
+                        format!("Enter a number from 1 to {count}")
+                    };
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+
+                if response.lost_focus() {
+                    if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                        self.send(Effect::OpenGoTo(false));
+                    } else if ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        if let Some(number) = target {
+                            self.send(Effect::GoTo(number - 1));
+                        } else {
+                            response.request_focus();
+                        }
+                    }
+                }
+
+                if local_draft != draft {
+                    self.send(Effect::DraftGoTo(local_draft));
+                }
+            });
+    }
+
+    /// Fills the central panel behind everything in it, as picked in Settings. Images cover
+    /// the panel, cropped to keep their aspect ratio.
+    fn paint_canvas_background(&self, ui: &egui::Ui) {
+        let rect = ui.clip_rect();
+        match &self.settings.canvas_bg {
+            CanvasBackground::Theme => {}
+            CanvasBackground::Color(color) => {
+                ui.painter().rect_filled(rect, 0.0, *color);
+            }
+            CanvasBackground::Image(_) => {
+                let Some((_, Some(texture))) = &self.canvas_texture else {
+                    return;
+                };
+                let image = texture.size_vec2();
+                let scale = (rect.width() / image.x).max(rect.height() / image.y);
+                let margin = (egui::Vec2::splat(1.0) - rect.size() / (image * scale)) / 2.0;
+                let uv = egui::Rect::from_min_max(
+                    margin.to_pos2(),
+                    (egui::Vec2::splat(1.0) - margin).to_pos2(),
+                );
+                ui.painter()
+                    .image(texture.id(), rect, uv, egui::Color32::WHITE);
+                let veil = ui.visuals().panel_fill.gamma_multiply(0.6);
+                ui.painter().rect_filled(rect, 0.0, veil);
+            }
+        }
+    }
+
+    /// The search box, hidden until opened with its button or Ctrl+F. Escape clears and hides it.
+    fn render_search(&self, ui: &mut egui::Ui) {
+        let toggle_pressed = ui.input_mut(|i| i.consume_shortcut(&SEARCH_SHORTCUT));
+
+        if !self.search_open {
+            let shortcut = ui.ctx().format_shortcut(&SEARCH_SHORTCUT);
+            if ui
+                .button(self.tr(Text::Search))
+                .on_hover_text(shortcut)
+                .clicked()
+                || toggle_pressed
+            {
+                self.send(Effect::OpenSearch);
+            }
+            self.render_match_count(ui);
+            return;
+        }
+
+        let mut local_query = self.search_query.clone();
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut local_query)
+                .id(search_id())
+                .hint_text(self.tr(Text::SearchHint))
+                .desired_width(150.0),
+        );
+
+        // The text edit gives up focus when it sees Escape
+        let escape_pressed =
+            response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Escape));
+        if escape_pressed || (toggle_pressed && response.has_focus()) {
+            self.send(Effect::CloseSearch);
+        } else if toggle_pressed {
+            response.request_focus();
+        } else if local_query != self.search_query {
+            self.send(Effect::SearchTodos(local_query));
+        }
+
+        // Laid out right to left, so these land before the search box
+        if ui
+            .selectable_label(self.search_regex, ".*")
+            .on_hover_text("Search with a regular expression")
+            .clicked()
+        {
+            self.send(Effect::ToggleSearchRegex);
+        }
+        if let Some(Err(err)) = &self.search_pattern {
+            ui.colored_label(ui.visuals().error_fg_color, "Invalid pattern")
+                .on_hover_text(err.to_string());
+        }
+
+        self.render_match_count(ui);
+    }
+
+    /// Counts of stickies added and completed each day this past week, across every board.
+    fn render_review(&self, ui: &mut egui::Ui) {
+        let today = self.settings.local(Utc::now()).date_naive();
+        let week = weekly_activity(&self.core.todos, today, &self.settings);
+        let added: usize = week.iter().map(|day| day.added).sum();
+        let completed: usize = week.iter().map(|day| day.completed).sum();
+        let open = self
+            .core
+            .todos
+            .iter()
+            .filter(|todo| !todo.checked && !todo.archived)
+            .count();
+
+        ui.heading("Last 7 days");
+        ui.label(format!(
+            "{added} added, {completed} completed, {open} still open"
+        ));
+        ui.add_space(10.0);
+
+        let added_color = ui.visuals().selection.bg_fill;
+        let completed_color = egui::Color32::from_rgb(110, 190, 110);
+        let most = week
+            .iter()
+            .map(|day| day.added.max(day.completed))
+            .max()
+            .unwrap_or_default()
+            .max(1);
+
+        egui::Grid::new("review_days").show(ui, |ui| {
+            for day in &week {
+                ui.label(day.day.weekday().to_string());
+                ui.vertical(|ui| {
+                    ui.spacing_mut().item_spacing.y = 2.0;
+                    for (count, color) in
+                        [(day.added, added_color), (day.completed, completed_color)]
+                    {
+                        ui.horizontal(|ui| {
+                            let width = 120.0 * count as f32 / most as f32;
+                            let (rect, _) = ui.allocate_exact_size(
+                                egui::vec2(width.max(1.0), 8.0),
+                                egui::Sense::hover(),
+                            );
+                            ui.painter().rect_filled(rect, 2.0, color);
+                            ui.small(count.to_string());
+                        });
+                    }
+                });
+                ui.end_row();
+            }
+        });
+
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            ui.colored_label(added_color, "■");
+            ui.small("added");
+            ui.colored_label(completed_color, "■");
+            ui.small("completed");
+        });
+    }
+
+    /// Whether everything's been saved, as a button that saves now.
+    fn render_save_status(&self, ui: &mut egui::Ui) {
+        let (status, color) = if self.locked.is_some() {
+            ("Locked", ui.visuals().weak_text_color())
+        } else if self.unsaved {
+            ("Unsaved changes", ui.visuals().warn_fg_color)
+        } else {
+            ("Saved", ui.visuals().weak_text_color())
+        };
+        let shortcut = ui.ctx().format_shortcut(&SAVE_SHORTCUT);
+        let button =
+            egui::Button::new(egui::RichText::new(status).small().color(color)).frame(false);
+        if ui
+            .add(button)
+            .on_hover_text(format!(
+                "Saved automatically every 30 seconds. Save now ({shortcut})"
+            ))
+            .clicked()
+        {
+            self.send(Effect::SaveNow);
+        }
+    }
+
+    /// Hides or shows the top bar and status bar, and handles the shortcuts for their buttons
+    /// that are needed either way. Escape also brings the bars back, unless it's closing
+    /// something else.
+    fn handle_chrome_shortcuts(&self, ctx: &egui::Context) {
+        let unfocused = ctx.memory(|mem| mem.focused().is_none());
+        let toggle_pressed = ctx.input_mut(|i| i.consume_shortcut(&CHROME_SHORTCUT));
+        let escape_pressed = self.chrome_hidden
+            && unfocused
+            && self.focus_mode.is_none()
+            && ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape));
+        if toggle_pressed || escape_pressed {
+            self.send(Effect::ToggleChrome);
+        }
+        // The search box is in the top bar, so searching brings it back
+        if self.chrome_hidden && ctx.input_mut(|i| i.consume_shortcut(&SEARCH_SHORTCUT)) {
+            self.send(Effect::ToggleChrome);
+            self.send(Effect::OpenSearch);
+        }
+
+        // Text fields handle these themselves while focused. Redo is checked first, since the
+        // undo shortcut also matches with shift held
+        let redo_pressed = unfocused && ctx.input_mut(|i| i.consume_shortcut(&REDO_SHORTCUT));
+        let undo_pressed = unfocused && ctx.input_mut(|i| i.consume_shortcut(&UNDO_SHORTCUT));
+        if redo_pressed && !self.redo_stack.is_empty() {
+            self.send(Effect::Redo);
+        }
+        if undo_pressed && !self.undo_stack.is_empty() {
+            self.send(Effect::Undo);
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&SAVE_SHORTCUT)) {
+            self.send(Effect::SaveNow);
+        }
+    }
+
+    /// The active board's stickies with alarms, in the order they go off, under headings for
+    /// when. Clicking one goes to it on the canvas.
+    fn render_agenda(&self, ui: &mut egui::Ui) {
+        let today = self.settings.local(Utc::now()).date_naive();
+        let mut upcoming: Vec<(&Todo, DateTime<FixedOffset>)> = self
+            .core
+            .todos
+            .iter()
+            .filter(|todo| todo.board == self.active_board && !todo.archived)
+            .filter_map(|todo| Some((todo, self.settings.local(todo.alarm?))))
+            .collect();
+        upcoming.sort_by_key(|(_, alarm)| *alarm);
+
+        ui.heading(self.tr(Text::Agenda));
+        if upcoming.is_empty() {
+            ui.weak("No alarms set. Set one from a sticky's right-click menu.");
+            return;
+        }
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            let mut shown_group = None;
+            for (todo, alarm) in upcoming {
+                let group = AgendaGroup::of(alarm.date_naive(), today);
+                if shown_group != Some(group) {
+                    ui.add_space(6.0);
+                    ui.strong(group.label());
+                    shown_group = Some(group);
+                }
+
+                let time = match group {
+                    AgendaGroup::Today | AgendaGroup::Tomorrow => alarm.format("%H:%M"),
+                    AgendaGroup::ThisWeek => alarm.format("%a %H:%M"),
+                    AgendaGroup::Later => alarm.format("%b %-d %H:%M"),
+                };
+                let label = if todo.is_concealed() {
+                    "Hidden sticky"
+                } else {
+                    &todo.label
+                };
+                let position = self
+                    .visible_todos()
+                    .position(|(_, visible)| visible.id == todo.id);
+                ui.horizontal(|ui| {
+                    ui.weak(time.to_string());
+                    let response = ui
+                        .add_enabled(position.is_some(), egui::Button::new(label).frame(false))
+                        .on_disabled_hover_text("Hidden by the search or completion filter");
+                    if let Some(position) = position.filter(|_| response.clicked()) {
+                        self.send(Effect::GoTo(position));
+                        if self.view != ViewMode::Canvas {
+                            self.send(Effect::SetView(ViewMode::Canvas));
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    /// Totals for the stickies shown, so the effort left follows the search and filters.
+    fn render_status_bar(&self, ui: &mut egui::Ui) {
+        let active: Vec<&Todo> = self
+            .visible_todos()
+            .map(|(_, todo)| todo)
+            .filter(|todo| !todo.checked)
+            .collect();
+        let effort: u32 = active
+            .iter()
+            .filter_map(|todo| todo.effort)
+            .map(u32::from)
+            .sum();
+
+        ui.horizontal(|ui| {
+            ui.weak(format!("{} active", active.len()));
+            if active.iter().any(|todo| todo.effort.is_some()) {
+                ui.separator();
+                ui.weak(format!("{effort} points remaining"));
+            }
+        });
+    }
+
+    /// A drop target for deleting stickies, outlined while one is being dragged.
+    fn render_trash(&self, ui: &mut egui::Ui) -> egui::Rect {
+        // Stickies are dragged by their handles, since their windows don't move themselves
+        let dragged = ui.ctx().dragged_id();
+        let dragging = self
+            .visible_todos()
+            .any(|(_, todo)| dragged == Some(drag_handle_id(todo.id)));
+
+        let response = ui
+            .add(egui::Button::new("🗑").sense(egui::Sense::hover()))
+            .on_hover_text("Drag a sticky here to delete it");
+        if dragging {
+            let over = ui
+                .input(|i| i.pointer.interact_pos())
+                .is_some_and(|pos| response.rect.contains(pos));
+            let stroke = if over {
+                egui::Stroke::new(2.0, ui.visuals().error_fg_color)
+            } else {
+                ui.visuals().selection.stroke
+            };
+            ui.painter().rect_stroke(
+                response.rect,
+                ui.visuals().widgets.inactive.rounding,
+                stroke,
+            );
+        }
+
+        response.rect
+    }
+
+    /// Steps between pages of stickies, once there are more than a page's worth.
+    fn render_pagination(&self, ui: &mut egui::Ui) {
+        let Some(page_size) = self.settings.page_size else {
+            return;
+        };
+        let total = self.visible_todos().count();
+        if total <= page_size {
+            return;
+        }
+
+        ui.separator();
+
+        let shown = self.page_range();
+        let page = shown.start / page_size;
+        if ui
+            .add_enabled(page > 0, egui::Button::new("◀"))
+            .on_hover_text("Previous page")
+            .clicked()
+        {
+            self.send(Effect::SetPage(page - 1));
+        }
+        ui.label(format!(
+            "Showing {}–{} of {total}",
+            shown.start + 1,
+            shown.end
+        ));
+        if ui
+            .add_enabled(shown.end < total, egui::Button::new("▶"))
+            .on_hover_text("Next page")
+            .clicked()
+        {
+            self.send(Effect::SetPage(page + 1));
+        }
+    }
+
+    /// How many of the board's stickies pass the search and filters, while any are active.
+    fn render_match_count(&self, ui: &mut egui::Ui) {
+        if !self.is_filtering() {
+            return;
+        }
+
+        let shown = self.visible_todos().count();
+        let total = self
+            .core
+            .todos
+            .iter()
+            .filter(|todo| todo.board == self.active_board)
+            .count();
+        ui.weak(format!("showing {shown} of {total}"));
+    }
+
+    /// Checks off every sticky the filters show, or unchecks them if they're all checked.
+    fn render_check_shown(&self, ui: &mut egui::Ui) {
+        let shown: Vec<(usize, &Todo)> = self.visible_todos().collect();
+        let all_checked = shown.iter().all(|(_, todo)| todo.checked);
+        let (text, hover) = if all_checked {
+            ("Uncheck shown", "Uncheck every sticky matching the filters")
+        } else {
+            ("Check shown", "Check off every sticky matching the filters")
+        };
+        if ui
+            .add_enabled(!shown.is_empty(), egui::Button::new(text))
+            .on_hover_text(hover)
+            .clicked()
+        {
+            self.send(check_all(&shown, !all_checked));
+        }
+    }
+
+    /// Colors, tags, moves, or deletes every sticky the filters show, as one undo step each.
+    fn render_shown_actions(&self, ui: &mut egui::Ui) {
+        let shown: Vec<(usize, &Todo)> = self.visible_todos().collect();
+        ui.add_enabled_ui(!shown.is_empty(), |ui| {
+            ui.menu_button(format!("All {} shown", shown.len()), |ui| {
+                ui.menu_button("Color", |ui| {
+                    color_swatch_row(ui, None, |ui, color| {
+                        self.send(color_all(&shown, Some(color)));
+                        ui.close_menu();
+                    });
+                    if ui.button("Default").clicked() {
+                        self.send(color_all(&shown, None));
+                        ui.close_menu();
+                    }
+                });
+
+                ui.menu_button("Add tag", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("#");
+                        let mut local_tag = self.shown_tag_draft.clone();
+                        let edit = ui.text_edit_singleline(&mut local_tag);
+                        let tag = local_tag.trim().trim_start_matches('#');
+                        let valid = !tag.is_empty() && !tag.contains(char::is_whitespace);
+                        let entered =
+                            edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                        if ui.add_enabled(valid, egui::Button::new("Add")).clicked()
+                            || (valid && entered)
+                        {
+                            self.send(tag_all(&shown, tag));
+                            self.send(Effect::DraftShownTag(String::new()));
+                            ui.close_menu();
+                        } else if local_tag != self.shown_tag_draft {
+                            self.send(Effect::DraftShownTag(local_tag));
+                        }
+                    });
+                });
+
+                if self.boards.len() > 1 {
+                    ui.menu_button("Move to board", |ui| {
+                        for (index, board) in self.boards.iter().enumerate() {
+                            if index == self.active_board {
+                                continue;
+                            }
+                            if ui.button(&board.name).clicked() {
+                                self.send(move_all(&shown, index));
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                }
+
+                ui.separator();
+                if ui.button(format!("{}…", self.tr(Text::Delete))).clicked() {
+                    self.send(Effect::ConfirmDeleteShown(true));
+                    ui.close_menu();
+                }
+            });
+        });
+    }
+
+    fn is_filtering(&self) -> bool {
+        !self.search_query.is_empty() || self.completion_filter != CompletionFilter::All
+    }
+
+    /// The positions within `visible_todos` of the page being shown.
+    fn page_range(&self) -> std::ops::Range<usize> {
+        page_range(
+            self.visible_todos().count(),
+            self.settings.page_size,
+            self.page,
+        )
+    }
+
+    /// The visible todos on the page being shown, which are the ones given windows or rows.
+    fn page_todos(&self) -> impl Iterator<Item = (usize, &Todo)> {
+        let range = self.page_range();
+        self.visible_todos().skip(range.start).take(range.len())
+    }
+
+    /// The todos that pass every active filter, paired with their index in `self.core.todos`,
+    /// in the active board's sort order. That isn't index order, so deletes of these go through
+    /// `delete_batch`.
+    fn visible_todos(&self) -> impl DoubleEndedIterator<Item = (usize, &Todo)> {
+        let query = self.search_query.to_lowercase();
+        let mut todos: Vec<_> = self
+            .core
+            .todos
+            .iter()
+            .enumerate()
+            .filter(|(_, todo)| {
+                todo.board == self.active_board
+                    && todo.hidden_until.is_none()
+                    && self.completion_filter.matches(todo)
+                    && self.matches_search(&todo.label, &query)
+            })
+            .collect();
+        self.sort_mode().sort(&mut todos);
+        todos.into_iter()
+    }
+
+    fn sort_mode(&self) -> SortMode {
+        self.boards
+            .get(self.active_board)
+            .map(|board| board.sort_mode)
+            .unwrap_or_default()
+    }
+
+    /// `trash` is where the top bar's trash is, so stickies dropped on it can be deleted.
+    /// `add_subtask_focused` is whether Tab was pressed to add a subtask to the focused sticky.
+    fn render_canvas(
+        &self,
+        ui: &mut egui::Ui,
+        trash: Option<egui::Rect>,
+        add_subtask_focused: bool,
+    ) {
+        // Space checks off the focused sticky and E archives it, as in Gmail, unless a widget
+        // like a text field wants the key
+        let unfocused = ui.memory(|mem| mem.focused().is_none());
+        let toggle_focused =
+            unfocused && ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Space));
+        let archive_focused =
+            unfocused && ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::E));
+
+        // A draft dragged from the top bar lands centered on where it's let go, over the
+        // canvas itself rather than over a sticky, with a ghost showing where until then
+        let drop_zone = ui.interact(
+            ui.max_rect(),
+            ui.id().with("draft_drop"),
+            egui::Sense::hover(),
+        );
+        let landing = ui
+            .input(|i| i.pointer.interact_pos())
+            .map(|pointer| self.draft_landing(ui.ctx(), pointer));
+        if let Some(draft) = drop_zone.dnd_release_payload::<DraftDrag>() {
+            if let Some(landing) = landing {
+                self.send(Effect::Batch(vec![
+                    Effect::AddTodo(draft.0.clone()),
+                    Effect::SnapTodo(self.core.todos.len(), Some(landing.min)),
+                ]));
+            }
+        } else if drop_zone.dnd_hover_payload::<DraftDrag>().is_some() {
+            if let Some(landing) = landing {
+                self.paint_landing_ghost(ui.ctx(), landing);
+            }
+        }
+
+        // Windows first shown in this order stack in it, restoring the stack on startup
+        let first = self.page_range().start;
+        let mut todos: Vec<_> = (first..)
+            .zip(self.page_todos())
+            .map(|(position, (index, todo))| (position, index, todo))
+            .collect();
+        todos.sort_by_key(|(_, _, todo)| todo.z);
+
+        let mut window_rects = vec![];
+        let mut peeled = vec![];
+        for (position, index, todo) in todos {
+            let number = self.settings.number_stickies.then_some(position + 1);
+            let focused = self.focused_todo == Some(todo.id);
+            let peeling = self.peeling.contains(&todo.id);
+            // Animated every frame, so the peel starts from rest rather than already finished
+            let peel = ui
+                .ctx()
+                .animate_bool_with_time(todo.id.with("peel"), peeling, PEEL_SECONDS);
+            if peeling && peel >= 1.0 {
+                peeled.push(index);
+            }
+            let popping = self.popping.contains(&todo.id);
+            let pop = ui
+                .ctx()
+                .animate_bool_with_time(todo.id.with("pop"), popping, POP_SECONDS);
+            if popping && pop >= 1.0 {
+                self.send(Effect::EndPop(todo.id));
+            }
+
+            // The window is shown below, so hover is checked against its layer as of last frame
+            let hovered = ui.input(|i| i.pointer.hover_pos()).is_some_and(|pos| {
+                ui.ctx().layer_id_at(pos) == Some(egui::LayerId::new(egui::Order::Middle, todo.id))
+            });
+            let awake = ui.ctx().animate_bool(
+                todo.id.with("awake"),
+                !self.settings.fade_idle || hovered || focused || popping || todo.pinned,
+            );
+            let opacity = (1.0 - peel) * egui::lerp(IDLE_OPACITY..=1.0, awake);
+            if focused && toggle_focused {
+                self.send(self.check_effect(index, todo));
+            }
+            if focused && archive_focused && !todo.archived {
+                self.send(Effect::ToggleArchived(index));
+            }
+            if focused && add_subtask_focused {
+                // The new subtask's field only shows while editing
+                if !todo.edit_mode {
+                    self.send(Effect::EditTodo(index));
+                }
+                self.send(Effect::AddSubtask(index, None));
+            }
+
+            let mut window = egui::Window::new(todo.label.clone())
+                .id(todo.id)
+                .resizable(false)
+                .collapsible(false)
+                .title_bar(false)
+                // Moved by its drag handle instead, so dragging over the body selects text
+                .movable(false);
+            if let Some(position) = todo.initial_position {
+                window = window.default_pos(position);
+            }
+            if let Some(position) = todo.snap_position {
+                window = window.current_pos(position);
+                self.send(Effect::SnapTodo(index, None));
+            }
+            let mut frame = egui::Frame::window(ui.style())
+                .rounding(self.settings.sticky_rounding)
+                .shadow(self.sticky_shadow(ui.style()));
+            if self.settings.compact {
+                frame.inner_margin *= COMPACT_SCALE;
+            }
+            if let Some(color) = todo.color {
+                frame = frame.fill(color);
+            }
+            if focused {
+                frame = frame.stroke(ui.visuals().selection.stroke);
+            }
+            if popping {
+                // Three flashes over the pop
+                let flash = (pop * 3.0 * std::f32::consts::PI).sin().abs();
+                let color = ui.visuals().warn_fg_color.gamma_multiply(flash);
+                frame = frame.stroke(egui::Stroke::new(3.0, color));
+            }
+            window = window.frame(frame.multiply_with_opacity(opacity));
+            let text_color = todo.color.map(contrasting_text_color);
+
+            let shown = window.show(ui.ctx(), |ui| {
+                let mut size = todo.size;
+                if self.settings.compact {
+                    compact_style(ui.style_mut());
+                    size *= COMPACT_SCALE;
+                }
+                // Note: I could not get `.fixed_size()`, `min_size()`, nor `.default_size()`
+                // to work on the Window instance itself, so this is a workaround
+                if self.settings.label_overflow == LabelOverflow::Grow {
+                    size = self.grown_size(ui, todo, size);
+                }
+                ui.set_min_size(size);
+                ui.set_max_size(size);
+                ui.set_opacity(opacity);
+
+                // The window itself senses nothing, so a background widget takes clicks
+                let background = ui.interact(
+                    ui.max_rect(),
+                    todo.id.with("background"),
+                    egui::Sense::click(),
+                );
+                // The second click of a double-click is left alone, so it doesn't undo the first
+                if self.settings.click_to_check
+                    && !todo.edit_mode
+                    && background.clicked()
+                    && !background.double_clicked()
+                {
+                    self.send(self.check_effect(index, todo));
+                }
+                background.context_menu(|ui| self.render_context_menu(ui, index, todo));
+
+                let handle = self.render_drag_handle(ui, todo, text_color);
+                if handle.dragged() {
+                    // Lands next frame, since this one's position is already set
+                    if let Some(rect) = ui.ctx().memory(|mem| mem.area_rect(todo.id)) {
+                        self.send(Effect::SnapTodo(
+                            index,
+                            Some(rect.min + handle.drag_delta()),
+                        ));
+                    }
+                }
+
+                let mut local_label = todo.label.clone();
+                let flip = ui.ctx().animate_bool_with_time(
+                    todo.id.with("flip"),
+                    todo.showing_back,
+                    FLIP_SECONDS,
+                );
+
+                // The checkbox only joins the label while the label is shown as plain text
+                let checkbox_with_label = self.settings.checkbox_placement
+                    == CheckboxPlacement::WithLabel
+                    && !todo.edit_mode
+                    && !todo.is_concealed();
+
+                // Hidden actions keep their space, so the body doesn't jump on hover
+                let show_actions =
+                    !self.settings.actions_on_hover || hovered || focused || todo.edit_mode;
+
+                ui.vertical(|ui| {
+                    ui.horizontal(|ui| {
+                        if !show_actions {
+                            ui.set_visible(false);
+                        }
+                        let id = if checkbox_with_label {
+                            "todo_actions_without_checkbox"
+                        } else {
+                            "todo_actions"
+                        };
+                        let container_width = ui.available_width();
+
+                        if !checkbox_with_label {
+                            let mut local_checked = todo.checked;
+                            if ui.checkbox(&mut local_checked, "").changed() {
+                                self.send(self.check_effect(index, todo));
+                            }
+                        }
+
+                        // We want to right justify the Edit and Delete buttons
+                        if let Some(stored_width) = self.calculated.get(id) {
+                            let offset = container_width - stored_width;
+                            ui.add_space(offset.max(0.0));
+                        }
+
+                        if todo.edit_mode {
+                            if ui.button(self.tr(Text::Save)).clicked() {
+                                self.send(Effect::SaveTodo(index, local_label.clone()));
+                                self.send(Effect::EditTodo(index));
+                            }
+                        } else if ui.button(self.tr(Text::Edit)).clicked() {
+                            self.send(Effect::EditTodo(index));
+                        }
+
+                        if ui.button(self.tr(Text::Delete)).clicked() {
+                            self.send(self.delete_effect(index, todo));
+                        }
+
+                        if self.calculated.get(id).is_none() {
+                            self.send(Effect::InsertCalculated(
+                                id.to_string(),
+                                ui.min_rect().width(),
+                            ));
+                        }
+                    });
+
+                    // Scroll long bodies, leaving room for the footer measured last frame.
+                    // egui persists the scroll offset, keyed by the sticky's id. Dragging the
+                    // body moves the sticky rather than scrolling, and leaves right-clicks to
+                    // the context menu behind it.
+                    let footer_height = self.calculated.get("todo_footer").copied();
+                    let body_height = ui.available_height()
+                        - footer_height.unwrap_or_default()
+                        - ui.spacing().item_spacing.y;
+                    egui::ScrollArea::vertical()
+                        .id_source(("todo_body", todo.id))
+                        .max_height(body_height)
+                        .auto_shrink([false, true])
+                        .drag_to_scroll(false)
+                        .show(ui, |ui| {
+                            // Swap sides halfway through, when the sticky is edge-on
+                            if flip > 0.5 {
+                                self.render_back(ui, index, todo, text_color);
+                                return;
+                            }
+
+                            let id = if checkbox_with_label {
+                                "todo_text_with_checkbox"
+                            } else {
+                                "todo_text"
+                            };
+                            // Subtasks and links need the room, so only a lone label is centered vertically
+                            if let Some(stored_height) = self.calculated.get(id) {
+                                if todo.subtasks.is_empty() && todo.links.is_empty() {
+                                    let offset = (body_height - stored_height) / 2.0;
+                                    ui.add_space(offset.max(0.0));
+                                }
+                            }
+
+                            let label = ui.vertical_centered(|ui| {
+                                if todo.edit_mode {
+                                    let edit = egui::TextEdit::singleline(&mut local_label)
+                                        .id(label_edit_id(todo.id));
+                                    if ui.add(edit).lost_focus()
+                                        && ui.input(|i| i.key_pressed(egui::Key::Enter))
+                                    {
+                                        self.send(Effect::SaveTodo(index, local_label.clone()));
+                                        self.send(Effect::EditTodo(index));
+                                    } else if local_label != todo.label {
+                                        self.send(Effect::SaveTodo(index, local_label.clone()));
+                                    }
+                                    self.render_label_length(ui, &local_label);
+                                    self.render_unsaved_label(ui, todo);
+                                } else if todo.is_concealed() {
+                                    self.render_concealed_label(ui, index, todo, text_color);
+                                } else if let Some(lang) = &todo.code_lang {
+                                    egui::Frame::none()
+                                        .fill(ui.visuals().code_bg_color)
+                                        .rounding(4.0)
+                                        .inner_margin(4.0)
+                                        .show(ui, |ui| {
+                                            let job =
+                                                highlight::highlight(ui.ctx(), &todo.label, lang);
+                                            ui.add(egui::Label::new(job).wrap(true));
+                                        });
+                                } else {
+                                    let mut text =
+                                        egui::RichText::new(numbered(&todo.label, number));
+                                    if let Some(text_color) = text_color {
+                                        text = text.color(text_color);
+                                    }
+                                    if checkbox_with_label {
+                                        let mut local_checked = todo.checked;
+                                        if ui.checkbox(&mut local_checked, text).changed() {
+                                            self.send(self.check_effect(index, todo));
+                                        }
+                                    } else if self.settings.label_overflow
+                                        == LabelOverflow::Truncate
+                                    {
+                                        ui.add(egui::Label::new(text).truncate(true))
+                                            .on_hover_text(&todo.label);
+                                    } else {
+                                        self.render_spelled_label(ui, text);
+                                    }
+
+                                    if todo.hidden && ui.small_button("Conceal").clicked() {
+                                        self.send(Effect::ToggleRevealed(index));
+                                    }
+                                }
+                            });
+
+                            if self.calculated.get(id).is_none()
+                                && todo.subtasks.is_empty()
+                                && todo.links.is_empty()
+                            {
+                                self.send(Effect::InsertCalculated(
+                                    id.to_string(),
+                                    label.response.rect.height(),
+                                ));
+                            }
+
+                            if !todo.is_concealed() {
+                                self.render_links(ui, index, todo);
+                                self.render_subtasks(ui, index, todo, text_color);
+                            }
+                        });
+
+                    let footer =
+                        ui.with_layout(egui::Layout::bottom_up(egui::Align::RIGHT), |ui| {
+                            ui.horizontal(|ui| {
+                                self.render_style_menu(ui, index, todo);
+
+                                let hide_label = if todo.hidden {
+                                    self.tr(Text::Unhide)
+                                } else {
+                                    self.tr(Text::Hide)
+                                };
+                                if ui
+                                    .small_button(hide_label)
+                                    .on_hover_text("Obscure the label on screen")
+                                    .clicked()
+                                {
+                                    self.send(Effect::ToggleHidden(index));
+                                }
+
+                                let flip_hint = if todo.showing_back {
+                                    "Show the front"
+                                } else {
+                                    "Show the notes on the back"
+                                };
+                                if ui
+                                    .small_button(self.tr(Text::Flip))
+                                    .on_hover_text(flip_hint)
+                                    .clicked()
+                                {
+                                    self.send(Effect::FlipTodo(index));
+                                }
+
+                                self.render_repeat_menu(ui, index, todo);
+                                self.render_effort_menu(ui, index, todo);
+
+                                // Nagging until acknowledged
+                                if todo.remaining_reminders > 0
+                                    && todo.alarm.is_some()
+                                    && ui
+                                        .small_button("🔔")
+                                        .on_hover_text(format!(
+                                            "Dismiss, skipping the {} repeats left",
+                                            todo.remaining_reminders
+                                        ))
+                                        .clicked()
+                                {
+                                    self.send(Effect::DismissReminders(index));
+                                }
+
+                                ui.with_layout(
+                                    egui::Layout::right_to_left(egui::Align::Center),
+                                    |ui| {
+                                        let local_created_at = self.settings.local(todo.created_at);
+                                        let mut elapsed = egui::RichText::new(
+                                            format_relative_time(Utc::now() - todo.created_at),
+                                        )
+                                        .small();
+                                        if let Some(text_color) = text_color {
+                                            elapsed = elapsed.color(text_color);
+                                        }
+                                        ui.label(elapsed).on_hover_text(
+                                            local_created_at.format("%c").to_string(),
+                                        );
+
+                                        let archives_in = self
+                                            .settings
+                                            .auto_archive_after
+                                            .and_then(|delay| todo.archives_in(delay, Utc::now()));
+                                        if let Some(remaining) = archives_in {
+                                            ui.weak("⏳").on_hover_text(format!(
+                                                "Archives in {}",
+                                                format_short_duration(remaining)
+                                            ));
+                                        }
+                                    },
+                                );
+                            });
+
+                            #[cfg(not(target_arch = "wasm32"))]
+                            self.render_attachment(ui, index, todo);
+                        });
+
+                    if footer_height.is_none() {
+                        self.send(Effect::InsertCalculated(
+                            "todo_footer".to_string(),
+                            footer.response.rect.height(),
+                        ));
+                    }
+                });
+
+                // Mid-flip, cover the sides so the sticky looks like it's turning over
+                let squeeze = (1.0 - 2.0 * flip).abs();
+                if squeeze < 1.0 {
+                    let rect = ui.spacing().window_margin.expand_rect(ui.max_rect());
+                    let cover = rect.width() * (1.0 - squeeze) / 2.0;
+                    let fill = todo.color.unwrap_or(ui.visuals().window_fill());
+                    let painter = ui.ctx().layer_painter(ui.layer_id());
+                    let left = egui::Rect::from_min_max(
+                        rect.left_top(),
+                        egui::pos2(rect.left() + cover, rect.bottom()),
+                    );
+                    let right = egui::Rect::from_min_max(
+                        egui::pos2(rect.right() - cover, rect.top()),
+                        rect.right_bottom(),
+                    );
+                    painter.rect_filled(left, 0.0, fill);
+                    painter.rect_filled(right, 0.0, fill);
+                    ui.ctx().request_repaint();
+                }
+
+                if peel > 0.0 {
+                    let rect = ui.spacing().window_margin.expand_rect(ui.max_rect());
+                    self.render_peel(ui, todo, rect, peel);
+                }
+
+                handle
+            });
+
+            if let Some(shown) = shown {
+                let Some(handle) = &shown.inner else {
+                    continue;
+                };
+                // Any press on the sticky focuses it, even one that lands on a button
+                let pressed_here = ui.input(|i| {
+                    i.pointer.any_pressed()
+                        && i.pointer.interact_pos().is_some_and(|pos| {
+                            ui.ctx().layer_id_at(pos) == Some(shown.response.layer_id)
+                        })
+                });
+                if pressed_here && !focused {
+                    self.send(Effect::FocusTodo(Some(todo.id)));
+                }
+                // egui raises the window itself; this keeps the stack for the next launch
+                if pressed_here {
+                    self.send(Effect::RaiseTodo(index));
+                }
+
+                self.render_celebration(ui.ctx(), todo, shown.response.rect);
+
+                let over_trash = trash.is_some_and(|trash| {
+                    ui.input(|i| i.pointer.interact_pos())
+                        .is_some_and(|pos| trash.contains(pos))
+                });
+                if over_trash && handle.drag_stopped() {
+                    self.send(self.delete_effect(index, todo));
+                } else if !over_trash {
+                    self.render_alignment_guides(ui.ctx(), index, shown.response.rect, handle);
+                }
+                window_rects.push(shown.response.rect);
+            }
+        }
+
+        // Zones are painted on the panel's background layer, so they stay behind every window
+        self.render_zones(ui, &window_rects);
+
+        // Collected in stacking order, which isn't index order
+        if !peeled.is_empty() {
+            self.send(delete_batch(peeled));
+        }
+    }
+
+    /// The theme's window shadow, faded by `Settings::sticky_shadow`.
+    fn sticky_shadow(&self, style: &egui::Style) -> egui::epaint::Shadow {
+        let shadow = style.visuals.window_shadow;
+        egui::epaint::Shadow {
+            color: shadow.color.gamma_multiply(self.settings.sticky_shadow),
+            ..shadow
+        }
+    }
+
+    /// Deletes a sticky from the canvas, peeling it away first unless that's turned off.
+    fn delete_effect(&self, index: usize, todo: &Todo) -> Effect {
+        if self.settings.peel_on_delete {
+            Effect::PeelTodo(todo.id)
+        } else {
+            Effect::DeleteTodo(index)
+        }
+    }
+
+    /// Warns when a label being edited runs past `Settings::label_warn_chars`, counting towards
+    /// the hard cap where it'll be cut short.
+    fn render_label_length(&self, ui: &mut egui::Ui, label: &str) {
+        let chars = label.chars().count();
+        if chars > self.settings.label_warn_chars {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("{chars}/{MAX_LABEL_CHARS} characters; long labels are hard to read"),
+            )
+            .on_hover_text("Put details on the back of the sticky instead");
+        }
+    }
+
+    /// A dot while the sticky's label has edits that aren't saved yet.
+    fn render_unsaved_label(&self, ui: &mut egui::Ui, todo: &Todo) {
+        if self.unsaved_labels.contains(&todo.id) {
+            let shortcut = ui.ctx().format_shortcut(&SAVE_SHORTCUT);
+            ui.small("●")
+                .on_hover_text(format!("Edited since the last save. Save now ({shortcut})"));
+        }
+    }
+
+    /// Dims everything behind the sticky with `id` and shows it enlarged in the middle of the
+    /// screen. Escape, or clicking the dimmed background, goes back.
+    fn render_focus_mode(&self, ctx: &egui::Context, id: egui::Id) {
+        let Some((index, todo)) = self.core.todos.iter().enumerate().find(|(_, t)| t.id == id)
+        else {
+            self.send(Effect::SetFocusMode(None));
+            return;
+        };
+
+        let nothing_focused = ctx.memory(|mem| mem.focused().is_none());
+        if nothing_focused
+            && ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape))
+        {
+            self.send(Effect::SetFocusMode(None));
+        }
+
+        // Catches clicks meant for the stickies behind, as well as dimming them
+        egui::Area::new(egui::Id::new("focus_backdrop"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(egui::Pos2::ZERO)
+            .show(ctx, |ui| {
+                let screen = ctx.screen_rect();
+                ui.painter()
+                    .rect_filled(screen, 0.0, egui::Color32::from_black_alpha(180));
+                let backdrop = ui.allocate_rect(screen, egui::Sense::click());
+                if backdrop.clicked() {
+                    self.send(Effect::SetFocusMode(None));
+                }
+            });
+
+        let card = egui::Id::new("focus_card");
+        ctx.move_to_top(egui::LayerId::new(egui::Order::Foreground, card));
+        egui::Area::new(card)
+            .order(egui::Order::Foreground)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                let mut frame = egui::Frame::window(ui.style()).inner_margin(24.0);
+                if let Some(color) = todo.color {
+                    frame = frame.fill(color);
+                }
+                let text_color = todo.color.map(contrasting_text_color);
+                frame.show(ui, |ui| {
+                    ui.set_width(420.0);
+                    ui.horizontal(|ui| {
+                        let mut local_checked = todo.checked;
+                        if ui.checkbox(&mut local_checked, "").changed() {
+                            self.send(self.check_effect(index, todo));
+                        }
+                        if todo.edit_mode {
+                            let mut local_label = todo.label.clone();
+                            ui.add(
+                                egui::TextEdit::singleline(&mut local_label)
+                                    .font(egui::TextStyle::Heading)
+                                    .desired_width(f32::INFINITY),
+                            );
+                            if local_label != todo.label {
+                                self.send(Effect::SaveTodo(index, local_label));
+                            }
+                        } else if todo.is_concealed() {
+                            self.render_concealed_label(ui, index, todo, text_color);
+                        } else {
+                            let mut text = egui::RichText::new(&todo.label).size(28.0);
+                            if let Some(text_color) = text_color {
+                                text = text.color(text_color);
+                            }
+                            ui.add(egui::Label::new(text).wrap(true));
+                        }
+                    });
+
+                    if !todo.is_concealed() {
+                        if !todo.back.is_empty() {
+                            ui.add_space(8.0);
+                            ui.label(&todo.back);
+                        }
+                        ui.add_space(8.0);
+                        self.render_subtasks(ui, index, todo, text_color);
+                    }
+
+                    if self.confirming_check == Some(todo.id) {
+                        ui.add_space(12.0);
+                        self.render_check_prompt(ui, index, todo);
+                    }
+
+                    ui.add_space(12.0);
+                    ui.horizontal(|ui| {
+                        let edit_label = if todo.edit_mode {
+                            self.tr(Text::Save)
+                        } else {
+                            self.tr(Text::Edit)
+                        };
+                        if ui.button(edit_label).clicked() {
+                            self.send(Effect::EditTodo(index));
+                        }
+                        if ui.button("Exit focus mode").clicked() {
+                            self.send(Effect::SetFocusMode(None));
+                        }
+                    });
+                });
+            });
+    }
+
+    /// Checks a todo off or back on, first asking for confirmation if it has open subtasks.
+    fn check_effect(&self, index: usize, todo: &Todo) -> Effect {
+        if !todo.checked && todo.open_subtasks() > 0 {
+            Effect::ConfirmCheck(Some(todo.id))
+        } else {
+            Effect::CheckTodo(index)
+        }
+    }
+
+    fn render_confirm_check(&self, ctx: &egui::Context, id: egui::Id) {
+        let Some((index, todo)) = self.core.todos.iter().enumerate().find(|(_, t)| t.id == id)
+        else {
+            // Deleted, or undone away, while the prompt was open
+            self.send(Effect::ConfirmCheck(None));
+            return;
+        };
+
+        egui::Window::new("Complete sticky?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| self.render_check_prompt(ui, index, todo));
+    }
+
+    fn render_check_prompt(&self, ui: &mut egui::Ui, index: usize, todo: &Todo) {
+        let open = todo.open_subtasks();
+        let plural = if open == 1 { "" } else { "s" };
+        ui.label(format!(
+            "This sticky has {open} open subtask{plural} — complete anyway?"
+        ));
+        ui.horizontal(|ui| {
+            if ui.button("Complete").clicked() {
+                self.send(Effect::CheckTodo(index));
+                self.send(Effect::ConfirmCheck(None));
+            }
+            if ui.button("Check all subtasks too").clicked() {
+                self.send(Effect::Batch(vec![
+                    Effect::CheckAllSubtasks(index),
+                    Effect::CheckTodo(index),
+                ]));
+                self.send(Effect::ConfirmCheck(None));
+            }
+            if ui.button("Cancel").clicked() {
+                self.send(Effect::ConfirmCheck(None));
+            }
+        });
+    }
+
+    /// The other stickies on the board, to link to or unlink from.
+    fn render_link_menu(&self, ui: &mut egui::Ui, index: usize, todo: &Todo) {
+        let others = self
+            .core
+            .todos
+            .iter()
+            .filter(|other| other.board == self.active_board && other.id != todo.id);
+        egui::ScrollArea::vertical()
+            .max_height(240.0)
+            .show(ui, |ui| {
+                let mut any = false;
+                for other in others {
+                    any = true;
+                    let mut linked = todo.links.contains(&other.id);
+                    if ui.checkbox(&mut linked, link_title(other)).changed() {
+                        self.send(if linked {
+                            Effect::LinkTodos(index, other.id)
+                        } else {
+                            Effect::UnlinkTodos(index, other.id)
+                        });
+                    }
+                }
+                if !any {
+                    ui.weak("No other stickies on this board");
+                }
+            });
+    }
+
+    /// A chip for each linked sticky, which goes to it when clicked.
+    fn render_links(&self, ui: &mut egui::Ui, index: usize, todo: &Todo) {
+        if todo.links.is_empty() {
+            return;
+        }
+        ui.horizontal_wrapped(|ui| {
+            for &link in &todo.links {
+                let Some(target) = self.core.todos.iter().find(|t| t.id == link) else {
+                    continue;
+                };
+                let position = self.visible_todos().position(|(_, t)| t.id == link);
+                let chip = ui
+                    .add_enabled(
+                        position.is_some(),
+                        egui::Button::new(
+                            egui::RichText::new(format!("🔗 {}", link_title(target))).small(),
+                        )
+                        .rounding(8.0),
+                    )
+                    .on_hover_text("Go to the linked sticky. Right-click to unlink")
+                    .on_disabled_hover_text("Hidden by the search, filter or board");
+                if let Some(position) = position.filter(|_| chip.clicked()) {
+                    self.send(Effect::GoTo(position));
+                }
+                chip.context_menu(|ui| {
+                    if ui.button("Unlink").clicked() {
+                        self.send(Effect::UnlinkTodos(index, link));
+                        ui.close_menu();
+                    }
+                });
+            }
+        });
+    }
+
+    /// Every action on a sticky, opened by right-clicking its background.
+    fn render_context_menu(&self, ui: &mut egui::Ui, index: usize, todo: &Todo) {
+        if !todo.is_concealed() && todo.code_lang.is_none() {
+            self.render_spelling_suggestions(ui, index, todo);
+        }
+
+        let edit_label = if todo.edit_mode {
+            self.tr(Text::Save)
+        } else {
+            self.tr(Text::Edit)
+        };
+        if ui.button(edit_label).clicked() {
+            self.send(Effect::EditTodo(index));
+            ui.close_menu();
+        }
+        if ui.button("Duplicate").clicked() {
+            self.send(Effect::DuplicateTodo(index));
+            ui.close_menu();
+        }
+        if !todo.subtasks.is_empty() {
+            ui.menu_button("Make into a board", |ui| {
+                if ui.button("Keep this sticky").clicked() {
+                    self.send(Effect::PromoteToBoard(todo.id, false));
+                    ui.close_menu();
+                }
+                if ui.button("Archive this sticky").clicked() {
+                    self.send(Effect::PromoteToBoard(todo.id, true));
+                    ui.close_menu();
+                }
+            })
+            .response
+            .on_hover_text("Turns each subtask into a sticky, on a new board named after this one");
+        }
+
+        let pin_label = if todo.pinned { "Unpin" } else { "Pin in place" };
+        if ui.button(pin_label).clicked() {
+            self.send(Effect::TogglePinned(index));
+            ui.close_menu();
+        }
+        ui.menu_button("Color", |ui| {
+            self.render_color_swatches(ui, index, todo);
+        });
+        if ui.button("Focus on this").clicked() {
+            self.send(Effect::SetFocusMode(Some(todo.id)));
+            ui.close_menu();
+        }
+        ui.menu_button("Link to", |ui| {
+            self.render_link_menu(ui, index, todo);
+        });
+        ui.menu_button("Code", |ui| {
+            if ui.radio(todo.code_lang.is_none(), "Plain text").clicked() {
+                self.send(Effect::SetCodeLang(index, None));
+                ui.close_menu();
+            }
+            for lang in highlight::LANGUAGES {
+                let selected = todo.code_lang.as_deref() == Some(lang);
+                if ui.radio(selected, lang).clicked() {
+                    self.send(Effect::SetCodeLang(index, Some(lang.to_owned())));
+                    ui.close_menu();
+                }
+            }
+        });
+        ui.menu_button("Alarm", |ui| {
+            for (name, minutes) in ALARM_DELAYS {
+                if ui.button(name).clicked() {
+                    let alarm = Utc::now() + chrono::Duration::minutes(minutes);
+                    self.send(Effect::SetAlarm(index, Some(alarm)));
+                    ui.close_menu();
+                }
+            }
+            if let Some(alarm) = todo.alarm {
+                ui.separator();
+                let alarm = self.settings.local(alarm);
+                ui.weak(format!("Goes off {}", alarm.format("%a %H:%M")));
+                ui.menu_button("Repeat until dismissed", |ui| {
+                    for (name, count, minutes) in REMINDER_REPEATS {
+                        let interval = Duration::from_secs(minutes * 60);
+                        let selected = todo.remaining_reminders == count
+                            && todo.reminder_interval == Some(interval);
+                        if ui.selectable_label(selected, name).clicked() {
+                            self.send(Effect::SetReminders(index, count, interval));
+                            ui.close_menu();
+                        }
+                    }
+                    if ui
+                        .selectable_label(todo.remaining_reminders == 0, "Don't repeat")
+                        .clicked()
+                    {
+                        self.send(Effect::SetReminders(index, 0, Duration::ZERO));
+                        ui.close_menu();
+                    }
+                });
+                if ui.button("Clear").clicked() {
+                    self.send(Effect::DismissReminders(index));
+                    ui.close_menu();
+                }
+            }
+        });
+        ui.menu_button("Snooze", |ui| {
+            for (name, minutes) in SNOOZE_DELAYS {
+                if ui.button(name).clicked() {
+                    let until = Utc::now() + chrono::Duration::minutes(minutes);
+                    self.send(Effect::SnoozeTodo(index, Some(until)));
+                    ui.close_menu();
+                }
+            }
+        })
+        .response
+        .on_hover_text("Take the sticky off the board for a while");
+        let archive_label = if todo.archived {
+            "Unarchive"
+        } else {
+            "Archive"
+        };
+        if ui.button(archive_label).clicked() {
+            self.send(Effect::ToggleArchived(index));
+            ui.close_menu();
+        }
+
+        ui.separator();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if ui
+            .button("Export and delete…")
+            .on_hover_text("Save the sticky to a Markdown file, then take it off the board")
+            .clicked()
+        {
+            ui.close_menu();
+            let mut dialog = rfd::FileDialog::new();
+            if let Some(dir) = &self.settings.offload_dir {
+                dialog = dialog.set_directory(dir);
+            }
+            if let Some(dir) = dialog.pick_folder() {
+                self.send(Effect::UpdateSettings(Box::new(Settings {
+                    offload_dir: Some(dir.clone()),
+                    ..self.settings.clone()
+                })));
+                self.send(Effect::ExportSingle(index, dir));
+            }
+        }
+        if ui.button(self.tr(Text::Delete)).clicked() {
+            self.send(self.delete_effect(index, todo));
+            ui.close_menu();
+        }
+    }
+
+    /// Curls a sticky's bottom-right corner up towards its middle as `peel` goes from 0 to 1.
+    fn render_peel(&self, ui: &egui::Ui, todo: &Todo, rect: egui::Rect, peel: f32) {
+        let fold = rect.width().min(rect.height()) * peel;
+        let corner = rect.right_bottom();
+        let bottom = egui::pos2(corner.x - fold, corner.y);
+        let side = egui::pos2(corner.x, corner.y - fold);
+        let tip = egui::pos2(corner.x - fold, corner.y - fold);
+
+        let opacity = 1.0 - peel;
+        let fill = todo.color.unwrap_or(ui.visuals().window_fill());
+        let stroke = ui.visuals().window_stroke();
+        let painter = ui.ctx().layer_painter(ui.layer_id());
+        // Cut the corner away, then lay its underside folded over the front
+        painter.add(egui::Shape::convex_polygon(
+            vec![bottom, corner, side],
+            ui.visuals().panel_fill,
+            egui::Stroke::NONE,
+        ));
+        painter.add(egui::Shape::convex_polygon(
+            vec![bottom, side, tip],
+            fill.linear_multiply(0.8).gamma_multiply(opacity),
+            egui::Stroke::new(stroke.width, stroke.color.gamma_multiply(opacity)),
+        ));
+    }
+
+    /// Shows dashed guides while a sticky's edges line up with another's, snapping to them on drop.
+    /// A ghost shows where the sticky will land once snapped.
+    fn render_alignment_guides(
+        &self,
+        ctx: &egui::Context,
+        index: usize,
+        rect: egui::Rect,
+        handle: &egui::Response,
+    ) {
+        if !handle.dragged() && !handle.drag_stopped() {
+            return;
+        }
+
+        let (offset, guides) = alignment_snap(rect, &self.other_window_rects(ctx, Some(index)));
+
+        if handle.drag_stopped() {
+            if offset != egui::Vec2::ZERO {
+                self.send(Effect::SnapTodo(index, Some(rect.min + offset)));
+            }
+            return;
+        }
+
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("alignment_guides"),
+        ));
+        let stroke = egui::Stroke::new(1.0, ctx.style().visuals.selection.stroke.color);
+        for [start, end] in guides {
+            painter.extend(egui::Shape::dashed_line(&[start, end], stroke, 4.0, 4.0));
+        }
+        // Unsnapped, the sticky lands right where it is, so there's nothing to show
+        if offset != egui::Vec2::ZERO {
+            self.paint_landing_ghost(ctx, rect.translate(offset));
+        }
+    }
+
+    /// Where the windows of the stickies on this page are, other than `except`'s.
+    fn other_window_rects(&self, ctx: &egui::Context, except: Option<usize>) -> Vec<egui::Rect> {
+        // Other windows may not have been shown yet this frame, so use their last positions
+        self.page_todos()
+            .filter(|(other_index, _)| Some(*other_index) != except)
+            .filter_map(|(_, other)| ctx.memory(|mem| mem.area_rect(other.id)))
+            .collect()
+    }
+
+    /// The window a draft dropped at `pointer` would get, centered on it and snapped to line up
+    /// with the other stickies.
+    fn draft_landing(&self, ctx: &egui::Context, pointer: egui::Pos2) -> egui::Rect {
+        let mut size = DEFAULT_STICKY_SIZE + ctx.style().spacing.window_margin.sum();
+        if self.settings.compact {
+            size *= COMPACT_SCALE;
+        }
+        let rect = egui::Rect::from_center_size(pointer, size);
+        let (offset, _) = alignment_snap(rect, &self.other_window_rects(ctx, None));
+        rect.translate(offset)
+    }
+
+    /// A translucent outline of a sticky where it will land once dropped.
+    fn paint_landing_ghost(&self, ctx: &egui::Context, rect: egui::Rect) {
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("landing_ghost"),
+        ));
+        let selection = ctx.style().visuals.selection;
+        painter.rect(
+            rect,
+            self.settings.sticky_rounding,
+            selection.bg_fill.gamma_multiply(0.25),
+            egui::Stroke::new(1.0, selection.stroke.color.gamma_multiply(0.6)),
+        );
+    }
+
+    /// The grip along a sticky's top, which moves the sticky when dragged unless it's pinned.
+    fn render_drag_handle(
+        &self,
+        ui: &mut egui::Ui,
+        todo: &Todo,
+        text_color: Option<egui::Color32>,
+    ) -> egui::Response {
+        let sense = if todo.pinned {
+            egui::Sense::hover()
+        } else {
+            egui::Sense::drag()
+        };
+        let size = egui::vec2(ui.available_width(), DRAG_HANDLE_HEIGHT);
+        let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let mut response = ui.interact(rect, drag_handle_id(todo.id), sense);
+        if !todo.pinned {
+            let cursor = if response.dragged() {
+                egui::CursorIcon::Grabbing
+            } else {
+                egui::CursorIcon::Grab
+            };
+            response = response.on_hover_cursor(cursor);
+        }
+
+        let visuals = ui.style().interact(&response);
+        let color = text_color.unwrap_or(visuals.fg_stroke.color);
+        let stroke = egui::Stroke::new(1.0, color.gamma_multiply(0.5));
+        let grip = egui::Rect::from_center_size(rect.center(), egui::vec2(24.0, 4.0));
+        for y in [grip.top(), grip.bottom()] {
+            ui.painter().hline(grip.x_range(), y, stroke);
+        }
+        response
+    }
+
+    /// The back of a sticky, holding longer notes that would crowd the front.
+    fn render_back(
+        &self,
+        ui: &mut egui::Ui,
+        index: usize,
+        todo: &Todo,
+        text_color: Option<egui::Color32>,
+    ) {
+        let mut heading = egui::RichText::new("Notes").small().strong();
+        if let Some(text_color) = text_color {
+            heading = heading.color(text_color);
+        }
+        ui.label(heading);
+
+        let mut local_back = todo.back.clone();
+        ui.add(
+            egui::TextEdit::multiline(&mut local_back)
+                .hint_text("Details, links, …")
+                .desired_rows(4)
+                .desired_width(f32::INFINITY),
+        );
+        if local_back != todo.back {
+            self.send(Effect::SaveBack(index, local_back));
+        }
+    }
+
+    /// Draws the labeled background zones, which can be dragged by their header and resized
+    /// from their bottom-right corner. A sticky belongs to a zone when its center is inside it.
+    fn render_zones(&self, ui: &mut egui::Ui, window_rects: &[egui::Rect]) {
+        for (index, zone) in self.zones.iter().enumerate() {
+            let id = ui.id().with(("zone", index));
+            let mut local_zone = zone.clone();
+
+            let members = window_rects
+                .iter()
+                .filter(|rect| zone.rect.contains(rect.center()))
+                .count();
+
+            let rounding = 8.0;
+            ui.painter()
+                .rect_filled(zone.rect, rounding, zone.color.gamma_multiply(0.2));
+            ui.painter()
+                .rect_stroke(zone.rect, rounding, egui::Stroke::new(1.0, zone.color));
+
+            let header = egui::Rect::from_min_size(
+                zone.rect.min,
+                egui::vec2(zone.rect.width(), ZONE_HEADER_HEIGHT),
+            );
+            ui.painter().text(
+                header.left_center() + egui::vec2(8.0, 0.0),
+                egui::Align2::LEFT_CENTER,
+                format!("{} ({members})", zone.name),
+                egui::FontId::proportional(14.0),
+                ui.visuals().text_color(),
+            );
+
+            let header_response = ui
+                .interact(header, id.with("header"), egui::Sense::click_and_drag())
+                .on_hover_cursor(egui::CursorIcon::Grab);
+            if header_response.dragged() {
+                local_zone.rect = local_zone.rect.translate(header_response.drag_delta());
+            }
+
+            let handle = egui::Rect::from_center_size(zone.rect.max, egui::Vec2::splat(12.0));
+            let handle_response = ui
+                .interact(handle, id.with("resize"), egui::Sense::drag())
+                .on_hover_cursor(egui::CursorIcon::ResizeNwSe);
+            if handle_response.dragged() {
+                let max = local_zone.rect.max + handle_response.drag_delta();
+                local_zone.rect.max = max.max(local_zone.rect.min + MIN_ZONE_SIZE);
+            }
+
+            header_response.context_menu(|ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Name: ");
+                    ui.text_edit_singleline(&mut local_zone.name);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Color: ");
+                    egui::color_picker::color_edit_button_srgba(
+                        ui,
+                        &mut local_zone.color,
+                        egui::color_picker::Alpha::Opaque,
+                    );
+                });
+                if ui.button("Delete zone").clicked() {
+                    self.send(Effect::DeleteZone(index));
+                    ui.close_menu();
+                }
+            });
+
+            if &local_zone != zone {
+                self.send(Effect::UpdateZone(index, local_zone));
+            }
+        }
+    }
+
+    /// Renders a sticky's subtasks as an indented outline. In edit mode the labels become text
+    /// fields and subtasks can be added or removed.
+    fn render_subtasks(
+        &self,
+        ui: &mut egui::Ui,
+        index: usize,
+        todo: &Todo,
+        text_color: Option<egui::Color32>,
+    ) {
+        if todo.subtasks.is_empty() && !todo.edit_mode {
+            return;
+        }
+
+        for (node_index, node) in todo.subtasks.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if node.children.is_empty() {
+                    ui.add_space(ui.spacing().icon_width);
+                } else {
+                    let icon = if node.collapsed { "⏵" } else { "⏷" };
+                    if ui.small_button(icon).clicked() {
+                        self.send(Effect::ToggleSubtaskCollapsed(index, node_index));
+                    }
+                }
+
+                let path = SubtaskPath::node(node_index);
+                self.render_subtask_row(ui, index, todo, path, node, text_color);
+            });
+
+            if node.collapsed {
+                continue;
+            }
+
+            ui.indent(("subtask_children", node_index), |ui| {
+                for (child_index, child) in node.children.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        let path = SubtaskPath::child(node_index, child_index);
+                        self.render_subtask_row(ui, index, todo, path, child, text_color);
+                    });
+                }
+            });
+        }
+
+        if todo.edit_mode && ui.small_button("+ Subtask").clicked() {
+            self.send(Effect::AddSubtask(index, None));
+        }
+    }
+
+    fn render_subtask_row(
+        &self,
+        ui: &mut egui::Ui,
+        index: usize,
+        todo: &Todo,
+        path: SubtaskPath,
+        node: &SubtaskNode,
+        text_color: Option<egui::Color32>,
+    ) {
+        let mut local_checked = node.checked;
+        if ui.checkbox(&mut local_checked, "").changed() {
+            self.send(Effect::CheckSubtask(index, path));
+        }
+
+        if todo.edit_mode {
+            let mut local_label = node.label.clone();
+            let field = ui.add(egui::TextEdit::singleline(&mut local_label).desired_width(60.0));
+            if self.new_subtask == Some((todo.id, path)) {
+                field.request_focus();
+            }
+            if local_label != node.label {
+                self.send(Effect::SaveSubtask(index, path, local_label));
+            }
+
+            // Only top-level subtasks can have children
+            if path.child.is_none()
+                && ui
+                    .small_button("+")
+                    .on_hover_text("Add nested subtask")
+                    .clicked()
+            {
+                self.send(Effect::AddSubtask(index, Some(path.node)));
+            }
+            if ui
+                .small_button("✖")
+                .on_hover_text("Delete subtask")
+                .clicked()
+            {
+                self.send(Effect::DeleteSubtask(index, path));
+            }
+        } else {
+            let mut text = egui::RichText::new(&node.label);
+            if node.checked {
+                text = text.strikethrough();
+            }
+            if let Some(text_color) = text_color {
+                text = text.color(text_color);
+            }
+            ui.add(egui::Label::new(text).wrap(true));
+        }
+    }
+
+    /// A wrapped label, with a red squiggle under misspelled words with `Settings::spellcheck`.
+    fn render_spelled_label(&self, ui: &mut egui::Ui, text: egui::RichText) -> egui::Response {
+        let misspelled = match self.dictionary() {
+            Some(dictionary) => dictionary.misspelled(text.text()),
+            None => vec![],
+        };
+        if misspelled.is_empty() {
+            return ui.add(egui::Label::new(text).wrap(true));
+        }
+
+        // Laid out here rather than by the label, to know where each word ends up
+        let label = text.text().to_owned();
+        let mut job = egui::WidgetText::from(text).into_layout_job(
+            ui.style(),
+            egui::FontSelection::Default,
+            ui.layout().vertical_align(),
+        );
+        job.wrap.max_width = ui.available_width();
+        job.halign = ui.layout().horizontal_placement();
+        let galley = ui.fonts(|fonts| fonts.layout_job(job));
+        let response = ui.add(egui::Label::new(galley.clone()));
+        let origin = match galley.job.halign {
+            egui::Align::LEFT => response.rect.left_top(),
+            egui::Align::Center => response.rect.center_top(),
+            egui::Align::RIGHT => response.rect.right_top(),
+        };
+
+        let stroke = egui::Stroke::new(1.0, egui::Color32::RED);
+        for range in misspelled {
+            let start = label[..range.start].chars().count();
+            let end = start + label[range].chars().count();
+            let from = galley.pos_from_ccursor(egui::text::CCursor::new(start));
+            let to = galley.pos_from_ccursor(egui::text::CCursor::new(end));
+            // A word broken across rows is left alone
+            if from.min.y != to.min.y {
+                continue;
+            }
+            let y = origin.y + from.max.y;
+            let points = (0..)
+                .map(|step| step as f32 * 2.0)
+                .take_while(|x| from.min.x + x <= to.min.x)
+                .map(|x| {
+                    let dy = if (x / 2.0) as usize % 2 == 0 {
+                        0.0
+                    } else {
+                        1.5
+                    };
+                    egui::pos2(origin.x + from.min.x + x, y + dy)
+                })
+                .collect();
+            ui.painter().add(egui::Shape::line(points, stroke));
+        }
+        response
+    }
+
+    /// Lists the label's misspelled words, each with the closest words to fix it with.
+    fn render_spelling_suggestions(&self, ui: &mut egui::Ui, index: usize, todo: &Todo) {
+        let Some(dictionary) = self.dictionary() else {
+            return;
+        };
+        let misspelled = dictionary.misspelled(&todo.label);
+        if misspelled.is_empty() {
+            return;
+        }
+
+        for range in misspelled {
+            let word = &todo.label[range.clone()];
+            ui.menu_button(format!("\"{word}\""), |ui| {
+                let suggestions = dictionary.suggest(word);
+                if suggestions.is_empty() {
+                    ui.weak("No suggestions");
+                }
+                for suggestion in suggestions {
+                    if ui.button(&suggestion).clicked() {
+                        let mut label = todo.label.clone();
+                        label.replace_range(range.clone(), &suggestion);
+                        self.send(Effect::SaveTodo(index, label));
+                        ui.close_menu();
+                    }
+                }
+            });
+        }
+        ui.separator();
+    }
+
+    /// The dictionary labels are checked against, with `Settings::spellcheck`.
+    fn dictionary(&self) -> Option<&'static Dictionary> {
+        self.settings.spellcheck.then(spellcheck::dictionary)
+    }
+
+    /// Draws a hidden sticky's label as dots, which reveal the label when clicked.
+    fn render_concealed_label(
+        &self,
+        ui: &mut egui::Ui,
+        index: usize,
+        todo: &Todo,
+        text_color: Option<egui::Color32>,
+    ) {
+        let dots = "•".repeat(todo.label.chars().count().clamp(3, 12));
+        let mut text = egui::RichText::new(dots);
+        if let Some(text_color) = text_color {
+            text = text.color(text_color);
+        }
+
+        let response = ui.add(egui::Label::new(text).sense(egui::Sense::click()));
+        if response.on_hover_text("Click to reveal").clicked() {
+            self.send(Effect::ToggleRevealed(index));
+        }
+    }
+
+    /// Preset repeat rules, plus a field for a custom RRULE.
+    fn render_repeat_menu(&self, ui: &mut egui::Ui, index: usize, todo: &Todo) {
+        ui.menu_button("🔁", |ui| {
+            for (name, rule) in REPEAT_PRESETS {
+                if ui
+                    .selectable_label(todo.recurrence.as_deref() == rule, name)
+                    .clicked()
+                {
+                    self.send(Effect::SetRecurrence(index, rule.map(str::to_owned)));
+                    ui.close_menu();
+                }
+            }
+
+            ui.separator();
+
+            ui.label("Custom rule (RRULE):");
+            let mut local_rule = todo.recurrence.clone().unwrap_or_default();
+            ui.add(egui::TextEdit::singleline(&mut local_rule).hint_text("FREQ=WEEKLY;BYDAY=MO"));
+            if local_rule != todo.recurrence.as_deref().unwrap_or_default() {
+                let rule = (!local_rule.is_empty()).then_some(local_rule);
+                self.send(Effect::SetRecurrence(index, rule));
+            }
+            if let Some(Err(err)) = todo.recurrence.as_ref().map(|r| r.parse::<Recurrence>()) {
+                ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+            }
+
+            if let Some(reopens_at) = todo.reopens_at {
+                let reopens_at = self.settings.local(reopens_at);
+                ui.weak(format!("Comes back {}", reopens_at.format("%a %b %e")));
+            }
+        })
+        .response
+        .on_hover_text("Repeat");
+    }
+
+    /// Picks the sticky's effort estimate, shown on the button once set.
+    fn render_effort_menu(&self, ui: &mut egui::Ui, index: usize, todo: &Todo) {
+        let title = match todo.effort {
+            Some(effort) => format!("{effort}pt"),
+            None => "pt".to_owned(),
+        };
+        ui.menu_button(title, |ui| {
+            if ui.selectable_label(todo.effort.is_none(), "None").clicked() {
+                self.send(Effect::SetEffort(index, None));
+                ui.close_menu();
+            }
+            for points in EFFORT_POINTS {
+                if ui
+                    .selectable_label(todo.effort == Some(points), points.to_string())
+                    .clicked()
+                {
+                    self.send(Effect::SetEffort(index, Some(points)));
+                    ui.close_menu();
+                }
+            }
+        })
+        .response
+        .on_hover_text("Effort");
+    }
+
+    /// The sticky's color and size.
+    fn render_style_menu(&self, ui: &mut egui::Ui, index: usize, todo: &Todo) {
+        ui.menu_button("Style", |ui| {
+            ui.label("Color");
+            self.render_color_swatches(ui, index, todo);
+
+            ui.separator();
+
+            ui.label("Size");
+            ui.horizontal(|ui| {
+                for (name, size) in STICKY_SIZES {
+                    if ui.selectable_label(todo.size == size, name).clicked() {
+                        self.send(Effect::ResizeTodo(index, size));
+                        ui.close_menu();
+                    }
+                }
+            });
+        });
+    }
+
+    /// The `#tags` used on this board, each with a way to color all its stickies at once.
+    /// Stickies tagged later keep their own color.
+    fn render_tags_menu(&self, ui: &mut egui::Ui) {
+        let mut tags: Vec<String> = self
+            .core
+            .todos
+            .iter()
+            .filter(|todo| todo.board == self.active_board)
+            .flat_map(Todo::tags)
+            .collect();
+        if tags.is_empty() {
+            return;
+        }
+        tags.sort();
+        tags.dedup();
+
+        ui.menu_button("Tags", |ui| {
+            for tag in tags {
+                ui.menu_button(format!("#{tag}"), |ui| {
+                    ui.label(format!("Color all '#{tag}' stickies"));
+                    color_swatch_row(ui, None, |ui, color| {
+                        self.send(self.color_tagged(&tag, Some(color)));
+                        ui.close_menu();
+                    });
+                    if ui.button("Default").clicked() {
+                        self.send(self.color_tagged(&tag, None));
+                        ui.close_menu();
+                    }
+                });
+            }
+        });
+    }
+
+    /// How many of this board's stickies are snoozed, with a way to wake them early.
+    fn render_snoozed_menu(&self, ui: &mut egui::Ui) {
+        let snoozed: Vec<(usize, &Todo, DateTime<Utc>)> = self
+            .core
+            .todos
+            .iter()
+            .enumerate()
+            .filter(|(_, todo)| todo.board == self.active_board)
+            .filter_map(|(index, todo)| Some((index, todo, todo.hidden_until?)))
+            .collect();
+        if snoozed.is_empty() {
+            return;
+        }
+
+        ui.menu_button(format!("💤 {}", snoozed.len()), |ui| {
+            for (index, todo, until) in &snoozed {
+                ui.horizontal(|ui| {
+                    ui.label(link_title(todo));
+                    let until = self.settings.local(*until);
+                    ui.weak(format!("until {}", until.format("%a %H:%M")));
+                    if ui.small_button("Wake").clicked() {
+                        self.send(Effect::SnoozeTodo(*index, None));
+                    }
+                });
+            }
+            ui.separator();
+            if ui.button("Wake all").clicked() {
+                let effects = snoozed
+                    .iter()
+                    .map(|(index, _, _)| Effect::SnoozeTodo(*index, None))
+                    .collect();
+                self.send(Effect::Batch(effects));
+                ui.close_menu();
+            }
+        })
+        .response
+        .on_hover_text("Snoozed stickies");
+    }
+
+    /// Sets the color of every sticky on this board carrying `tag`, as one undo step.
+    fn color_tagged(&self, tag: &str, color: Option<egui::Color32>) -> Effect {
+        Effect::Batch(
+            self.core
+                .todos
+                .iter()
+                .enumerate()
+                .filter(|(_, todo)| {
+                    todo.board == self.active_board && todo.tags().any(|other| other == tag)
+                })
+                .map(|(index, _)| Effect::SetColor(index, color))
+                .collect(),
+        )
+    }
+
+    /// Preset sticky colors, plus resetting to the theme's default.
+    fn render_color_swatches(&self, ui: &mut egui::Ui, index: usize, todo: &Todo) {
+        color_swatch_row(ui, todo.color, |ui, color| {
+            self.send(Effect::SetColor(index, Some(color)));
+            ui.close_menu();
+        });
+
+        if ui.button("Default").clicked() {
+            self.send(Effect::SetColor(index, None));
+            ui.close_menu();
+        }
+    }
+
+    /// The sticky's size, made taller if needed to fit its whole label wrapped in its width,
+    /// with room for the action row and footer above and below.
+    fn grown_size(&self, ui: &egui::Ui, todo: &Todo, size: egui::Vec2) -> egui::Vec2 {
+        if todo.is_concealed() {
+            return size;
+        }
+        let style = if todo.code_lang.is_some() {
+            egui::TextStyle::Monospace
+        } else {
+            egui::TextStyle::Body
+        };
+        let font = style.resolve(ui.style());
+        let label = ui.fonts(|fonts| {
+            fonts.layout(todo.label.clone(), font, egui::Color32::PLACEHOLDER, size.x)
+        });
+        let spacing = ui.spacing();
+        let footer = self.calculated.get("todo_footer").copied();
+        let chrome = DRAG_HANDLE_HEIGHT
+            + spacing.interact_size.y
+            + footer.unwrap_or_default()
+            + 4.0 * spacing.item_spacing.y;
+        egui::vec2(size.x, size.y.max(label.size().y + chrome))
+    }
+
+    /// Bursts confetti out of a sticky's window for a moment after it's checked off.
+    fn render_celebration(&self, ctx: &egui::Context, todo: &Todo, rect: egui::Rect) {
+        let quiet = self.boards.get(todo.board).is_some_and(|board| board.quiet);
+        if !self.settings.celebrate_completion || quiet {
+            return;
+        }
+
+        // Only animates on a change, so stickies loaded as checked don't celebrate
+        let progress = ctx.animate_bool_with_time(
+            egui::Id::new(("celebration", todo.id)),
+            todo.checked,
+            CELEBRATION_SECONDS,
+        );
+        if !todo.checked || progress >= 1.0 {
+            return;
+        }
+
+        // Paint on a foreground layer that doesn't sense input, so the sticky stays interactive
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new(("celebration_layer", todo.id)),
+        ));
+        let colors = [
+            egui::Color32::from_rgb(255, 99, 132),
+            egui::Color32::from_rgb(255, 205, 86),
+            egui::Color32::from_rgb(75, 192, 192),
+            egui::Color32::from_rgb(54, 162, 235),
+        ];
+        let particles = 16;
+        for i in 0..particles {
+            let angle = std::f32::consts::TAU * i as f32 / particles as f32;
+            let distance = rect.width().max(rect.height()) * 0.75 * progress;
+            let color = colors[i % colors.len()].gamma_multiply(1.0 - progress);
+            painter.circle_filled(
+                rect.center() + egui::Vec2::angled(angle) * distance,
+                2.0 + 3.0 * (1.0 - progress),
+                color,
+            );
+        }
+    }
+
+    /// Shows a sticky's attached file or folder as a chip, or a menu to attach one.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn render_attachment(&self, ui: &mut egui::Ui, index: usize, todo: &Todo) {
+        ui.horizontal(|ui| match &todo.attachment {
+            Some(path) => {
+                let name = path
+                    .file_name()
+                    .unwrap_or(path.as_os_str())
+                    .to_string_lossy();
+                egui::Frame::none()
+                    .fill(ui.visuals().faint_bg_color)
+                    .rounding(8.0)
+                    .inner_margin(egui::Margin::symmetric(6.0, 2.0))
+                    .show(ui, |ui| {
+                        ui.set_max_width(70.0);
+                        ui.add(egui::Label::new(egui::RichText::new(name).small()).truncate(true))
+                            .on_hover_text(path.display().to_string());
+                    });
+
+                // The file may have been moved or deleted since it was attached
+                let exists = path.exists();
+                if ui
+                    .add_enabled(exists, egui::Button::new("Open").small())
+                    .on_disabled_hover_text("File not found")
+                    .clicked()
+                {
+                    if let Err(err) = open::that_detached(path) {
+                        log::warn!("Failed to open {}: {err}", path.display());
+                    }
+                }
+
+                if ui.small_button("✖").on_hover_text("Detach").clicked() {
+                    self.send(Effect::AttachFile(index, None));
+                }
+            }
+            None => {
+                ui.menu_button("Attach", |ui| {
+                    let picked = if ui.button("File…").clicked() {
+                        rfd::FileDialog::new().pick_file()
+                    } else if ui.button("Folder…").clicked() {
+                        rfd::FileDialog::new().pick_folder()
+                    } else {
+                        return;
+                    };
+
+                    ui.close_menu();
+                    if let Some(path) = picked {
+                        self.send(Effect::AttachFile(index, Some(path)));
+                    }
+                });
+            }
+        });
+    }
+
+    /// Lists the visible titles in a side panel, and shows every field of the selected todo
+    /// beside it. Up and Down change the selection.
+    fn render_detail(&self, ui: &mut egui::Ui) {
+        let todos: Vec<(usize, &Todo)> = self.page_todos().collect();
+        let selected = todos
+            .iter()
+            .position(|(_, todo)| Some(todo.id) == self.selected_detail);
+
+        // Text fields keep the arrow keys for themselves while focused
+        if !todos.is_empty() && ui.memory(|mem| mem.focused().is_none()) {
+            let step = ui.input(|i| {
+                i.key_pressed(egui::Key::ArrowDown) as isize
+                    - i.key_pressed(egui::Key::ArrowUp) as isize
+            });
+            if step != 0 {
+                let next = match selected {
+                    Some(selected) => selected.saturating_add_signed(step).min(todos.len() - 1),
+                    None => 0,
+                };
+                self.send(Effect::SelectDetail(Some(todos[next].1.id)));
+            }
+        }
+
+        egui::SidePanel::left("detail_titles")
+            .default_width(200.0)
+            .show_inside(ui, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (_, todo) in &todos {
+                        let label = if todo.is_concealed() {
+                            "••••••"
+                        } else {
+                            &todo.label
+                        };
+                        let mut title = egui::RichText::new(label);
+                        if todo.checked {
+                            title = title.strikethrough();
+                        }
+                        let is_selected = Some(todo.id) == self.selected_detail;
+                        if ui.selectable_label(is_selected, title).clicked() {
+                            self.send(Effect::SelectDetail(Some(todo.id)));
+                        }
+                    }
+                });
+            });
+
+        let Some(&(index, todo)) = selected.map(|selected| &todos[selected]) else {
+            ui.centered_and_justified(|ui| {
+                ui.weak("Select a sticky to see all of it");
+            });
+            return;
+        };
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.horizontal(|ui| {
+                let mut local_checked = todo.checked;
+                if ui.checkbox(&mut local_checked, "").changed() {
+                    self.send(self.check_effect(index, todo));
+                }
+
+                if todo.is_concealed() {
+                    self.render_concealed_label(ui, index, todo, None);
+                } else {
+                    let mut local_label = todo.label.clone();
+                    ui.add(
+                        egui::TextEdit::singleline(&mut local_label)
+                            .font(egui::TextStyle::Heading)
+                            .desired_width(f32::INFINITY),
+                    );
+                    self.render_label_length(ui, &local_label);
+                    self.render_unsaved_label(ui, todo);
+                    if local_label != todo.label {
+                        self.send(Effect::SaveTodo(index, local_label));
+                    }
+                }
+            });
+
+            ui.horizontal(|ui| {
+                self.render_style_menu(ui, index, todo);
+                self.render_repeat_menu(ui, index, todo);
+                self.render_effort_menu(ui, index, todo);
+
+                let hide_label = if todo.hidden {
+                    self.tr(Text::Unhide)
+                } else {
+                    self.tr(Text::Hide)
+                };
+                if ui.button(hide_label).clicked() {
+                    self.send(Effect::ToggleHidden(index));
+                }
+                let edit_label = if todo.edit_mode {
+                    "Done editing subtasks"
+                } else {
+                    "Edit subtasks"
+                };
+                if ui.button(edit_label).clicked() {
+                    self.send(Effect::EditTodo(index));
+                }
+            });
+
+            ui.add_space(5.0);
+            self.render_back(ui, index, todo, None);
+            ui.add_space(5.0);
+            self.render_subtasks(ui, index, todo, None);
+
+            ui.separator();
+
+            let created_at = self.settings.local(todo.created_at);
+            let updated_at = self.settings.local(todo.updated_at);
+            ui.weak(format!("Added {}", created_at.format("%c")));
+            ui.weak(format!("Last changed {}", updated_at.format("%c")));
+
+            if ui.button(self.tr(Text::Delete)).clicked() {
+                self.send(Effect::DeleteTodo(index));
+            }
+        });
+    }
+
+    fn render_list(&self, ui: &mut egui::Ui) {
+        let sort_mode = self.sort_mode();
+        // Moving rows by hand only shows in the order they were added
+        let manual = sort_mode == SortMode::Created;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Sort by")
+                .selected_text(sort_mode.name())
+                .show_ui(ui, |ui| {
+                    for mode in SortMode::ALL {
+                        if ui
+                            .selectable_label(mode == sort_mode, mode.name())
+                            .clicked()
+                        {
+                            self.send(Effect::SetSortMode(self.active_board, mode));
+                        }
+                    }
+                });
+            if manual {
+                ui.weak("Tip: drag a row by its handle, or hover it and press Alt+Up or Alt+Down");
+            }
+        });
+        ui.add_space(5.0);
+
+        // Restored once on startup; afterwards egui keeps the offset itself
+        let mut scroll = egui::ScrollArea::vertical().id_source("list_scroll");
+        if !self.session_restored {
+            scroll = scroll.vertical_scroll_offset(self.list_scroll);
+        }
+        let first = self.page_range().start;
+        let output = scroll.show(ui, |ui| {
+            for (position, (index, todo)) in (first..).zip(self.page_todos()) {
+                let number = self.settings.number_stickies.then_some(position + 1);
+                // Scope widget ids to the todo so keyboard focus follows the row when it moves
+                ui.push_id(todo.id, |ui| {
+                    let row = ui.horizontal(|ui| {
+                        // Only the handle starts a drag, so the rest of the row stays clickable
+                        if manual {
+                            ui.dnd_drag_source(ui.id().with("drag_handle"), index, |ui| {
+                                let (rect, _) = ui.allocate_exact_size(
+                                    egui::vec2(10.0, 12.0),
+                                    egui::Sense::hover(),
+                                );
+                                let stroke = ui.visuals().widgets.inactive.fg_stroke;
+                                for i in 0..3 {
+                                    let y = rect.top() + 2.0 + 4.0 * i as f32;
+                                    ui.painter().hline(rect.x_range(), y, stroke);
+                                }
+                            })
+                            .response
+                            .on_hover_text("Drag to reorder");
+                        }
+
+                        let mut local_checked = todo.checked;
+                        let checkbox = ui.checkbox(&mut local_checked, "");
+                        if checkbox.changed() {
+                            self.send(self.check_effect(index, todo));
+                        }
+
+                        if todo.edit_mode {
+                            let mut local_label = todo.label.clone();
+                            if ui.text_edit_singleline(&mut local_label).lost_focus()
+                                && ui.input(|i| i.key_pressed(egui::Key::Enter))
+                            {
+                                self.send(Effect::EditTodo(index));
+                            }
+                            self.render_label_length(ui, &local_label);
+                            self.render_unsaved_label(ui, todo);
+
+                            if local_label != todo.label {
+                                self.send(Effect::SaveTodo(index, local_label));
+                            }
+                        } else if todo.is_concealed() {
+                            self.render_concealed_label(ui, index, todo, None);
+                        } else {
+                            self.render_spelled_label(ui, numbered(&todo.label, number).into());
+                        }
+
+                        let actions = ui.with_layout(
+                            egui::Layout::right_to_left(egui::Align::Center),
+                            |ui| {
+                                let delete = ui.button(self.tr(Text::Delete));
+                                if delete.clicked() {
+                                    self.send(Effect::DeleteTodo(index));
+                                }
+
+                                let edit = ui.button(if todo.edit_mode {
+                                    self.tr(Text::Save)
+                                } else {
+                                    self.tr(Text::Edit)
+                                });
+                                if edit.clicked() {
+                                    self.send(Effect::EditTodo(index));
+                                }
+
+                                delete.has_focus() || edit.has_focus()
+                            },
+                        );
+
+                        checkbox.has_focus() || actions.inner
+                    });
+
+                    // Dropping on a row's top half inserts above it, on its bottom half below it
+                    let rect = row.response.rect;
+                    let drop_position = |pointer: egui::Pos2| {
+                        if pointer.y < rect.center().y {
+                            (index, rect.top())
+                        } else {
+                            (index + 1, rect.bottom())
+                        }
+                    };
+                    if let Some(pointer) = ui.ctx().pointer_interact_pos() {
+                        if row.response.dnd_hover_payload::<usize>().is_some() {
+                            let (_, y) = drop_position(pointer);
+                            let stroke = ui.visuals().selection.stroke;
+                            ui.painter().hline(rect.x_range(), y, stroke);
+                        }
+                        if let Some(from) = row.response.dnd_release_payload::<usize>() {
+                            let (to, _) = drop_position(pointer);
+                            self.send(Effect::Reorder(*from, to));
+                        }
+                    }
+
+                    // Don't steal Alt+arrow presses from a label being edited
+                    let row_active =
+                        manual && (row.response.hovered() || row.inner) && !todo.edit_mode;
+                    if row_active {
+                        if ui.input_mut(|i| i.consume_key(egui::Modifiers::ALT, egui::Key::ArrowUp))
+                        {
+                            self.send(Effect::MoveUp(index));
+                        }
+                        if ui.input_mut(|i| {
+                            i.consume_key(egui::Modifiers::ALT, egui::Key::ArrowDown)
+                        }) {
+                            self.send(Effect::MoveDown(index));
+                        }
+                    }
+                });
+
+                ui.separator();
+            }
+        });
+        if output.state.offset.y != self.list_scroll {
+            self.send(Effect::ScrollList(output.state.offset.y));
+        }
+    }
+}
+
+enum Effect {
+    DraftTodo(String),
+    /// Several effects applied together, undone as one step.
+    Batch(Vec<Effect>),
+    Undo,
+    Redo,
+    AddTodo(String),
+    EditTodo(usize),
+    SaveTodo(usize, String),
+    SaveBack(usize, String),
+    FlipTodo(usize),
+    CheckTodo(usize),
+    DuplicateTodo(usize),
+    ToggleArchived(usize),
+    TogglePinned(usize),
+    ToggleHidden(usize),
+    ToggleRevealed(usize),
+    SetColor(usize, Option<egui::Color32>),
+    MoveToBoard(usize, usize),
+    ResizeTodo(usize, egui::Vec2),
+    SetRecurrence(usize, Option<String>),
+    SetEffort(usize, Option<u8>),
+    SetCodeLang(usize, Option<String>),
+    /// Links a todo to the todo with the given id.
+    LinkTodos(usize, egui::Id),
+    UnlinkTodos(usize, egui::Id),
+    AddSubtask(usize, Option<usize>),
+    SaveSubtask(usize, SubtaskPath, String),
+    CheckSubtask(usize, SubtaskPath),
+    DeleteSubtask(usize, SubtaskPath),
+    ToggleSubtaskCollapsed(usize, usize),
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))] // no file system access on web
+    AttachFile(usize, Option<PathBuf>),
+    SetAlarm(usize, Option<chrono::DateTime<Utc>>),
+    /// Repeats a sticky's alarm the given number of times, the given time apart.
+    SetReminders(usize, u32, Duration),
+    DismissReminders(usize),
+    /// Hides the todo from the board until the given time, or shows it again with `None`.
+    SnoozeTodo(usize, Option<chrono::DateTime<Utc>>),
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))] // no file system access on web
+    ImportStickies(PathBuf),
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))] // no file system access on web
+    ImportBoard(PathBuf),
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))] // no file system access on web
+    ExportBoard(usize, PathBuf),
+    /// Saves a PNG of the central panel to the path, once the screenshot it asks for arrives.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))] // no file system access on web
+    ExportImage(PathBuf),
+    /// Writes one sticky to a Markdown file in the given folder, then takes it off the board.
+    /// If the file can't be written, the sticky stays and the failure is shown instead.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))] // no file system access on web
+    ExportSingle(usize, PathBuf),
+    /// Raises a sticky above the others and flashes its border for a moment.
+    PopTodo(egui::Id),
+    EndPop(egui::Id),
+    /// Starts a sticky's peel animation, after which it's deleted.
+    PeelTodo(egui::Id),
+    DeleteTodo(usize),
+    SnapTodo(usize, Option<egui::Pos2>),
+    FocusTodo(Option<egui::Id>),
+    ScrollList(f32),
+    /// Saves at the end of the frame, without waiting for the next autosave.
+    SaveNow,
+    /// Stacks a sticky above the others, as egui does when it's clicked.
+    RaiseTodo(usize),
+    SelectDetail(Option<egui::Id>),
+    Reorder(usize, usize),
+    MoveUp(usize),
+    MoveDown(usize),
+
+    AddZone,
+    UpdateZone(usize, Zone),
+    DeleteZone(usize),
+
+    SwitchBoard(usize),
+    AddBoard,
+    /// Makes a board named after the todo with this id, with a todo for each of its subtasks,
+    /// archiving the todo too if `true`.
+    PromoteToBoard(egui::Id, bool),
+    RenameBoard(usize, String),
+    SetSortMode(usize, SortMode),
+    SetBoardColor(usize, Option<egui::Color32>),
+    SetBoardQuiet(usize, bool),
+    DuplicateBoard(usize),
+
+    ConfirmReset(bool),
+    /// Asks before checking off the todo with this id, which has open subtasks.
+    ConfirmCheck(Option<egui::Id>),
+    /// Asks before deleting every sticky the filters show.
+    ConfirmDeleteShown(bool),
+    DraftShownTag(String),
+    CheckAllSubtasks(usize),
+    ResetAll,
+
+    DraftPassphrase(String),
+    Unlock(String),
+    DiscardLocked,
+    /// Reloads the storage file changed on disk, or saves over its changes.
+    #[cfg(not(target_arch = "wasm32"))]
+    ResolveStorageChange(bool),
+    /// Shows why a reminder email failed to send, or hides it with `None`.
+    #[cfg(not(target_arch = "wasm32"))]
+    SetEmailError(Option<String>),
+    /// Shows why a sticky couldn't be exported, or hides it with `None`.
+    SetExportError(Option<String>),
+    OpenEncryptionDialog(bool),
+    EnableEncryption(String),
+    DisableEncryption,
+
+    OpenSearch,
+    ToggleSearchRegex,
+    CloseSearch,
+    SearchTodos(String),
+    SetCompletionFilter(CompletionFilter),
+    SetPage(usize),
+    ToggleReview,
+    ToggleAgenda,
+    ToggleChrome,
+    SetFocusMode(Option<egui::Id>),
+    OpenGoTo(bool),
+    DraftGoTo(String),
+    /// Focuses and raises the sticky at this position among the visible ones, counting from 0.
+    GoTo(usize),
+
+    DismissRecovery,
+
+    SetView(ViewMode),
+    UpdateSettings(Box<Settings>),
+    InsertCalculated(String, f32),
+}
+
+impl Effect {
+    /// Where the effect goes among those sent in the same frame, which all name todos by their
+    /// index as of that frame's render. Effects that change a todo in place go first, then ones
+    /// that add or move todos, then deletes from the highest index down, so that no delete
+    /// shifts the todo another effect names. Effects that swap out every todo, like Undo, go
+    /// last. Effects that tie keep the order they were sent in.
+    ///
+    /// A batch is built to be applied in its own order, so it's placed as a whole, going where
+    /// the latest of its effects would.
+    fn apply_order(&self) -> (u8, std::cmp::Reverse<usize>) {
+        use std::cmp::Reverse;
+        match self {
+            Effect::AddTodo(_)
+            | Effect::ImportStickies(_)
+            | Effect::ImportBoard(_)
+            | Effect::PromoteToBoard(..)
+            | Effect::DuplicateTodo(_)
+            | Effect::Reorder(..)
+            | Effect::MoveUp(_)
+            | Effect::MoveDown(_) => (1, Reverse(0)),
+            Effect::DeleteTodo(index) | Effect::ExportSingle(index, _) => (2, Reverse(*index)),
+            Effect::Undo | Effect::Redo | Effect::ResetAll => (3, Reverse(0)),
+            Effect::Batch(effects) => effects
+                .iter()
+                .map(Effect::apply_order)
+                .max()
+                .unwrap_or((0, Reverse(0))),
+            _ => (0, Reverse(0)),
+        }
+    }
+
+    /// Whether the effect changes anything that's saved, rather than only what's on screen.
+    fn changes_saved_state(&self) -> bool {
+        !matches!(
+            self,
+            Effect::ToggleRevealed(_)
+                | Effect::PopTodo(_)
+                | Effect::EndPop(_)
+                | Effect::PeelTodo(_)
+                | Effect::SnapTodo(..)
+                | Effect::ExportBoard(..)
+                | Effect::ExportImage(_)
+                | Effect::SelectDetail(_)
+                | Effect::ConfirmReset(_)
+                | Effect::ConfirmCheck(_)
+                | Effect::ConfirmDeleteShown(_)
+                | Effect::DraftShownTag(_)
+                | Effect::DraftPassphrase(_)
+                | Effect::OpenEncryptionDialog(_)
+                | Effect::SetPage(_)
+                | Effect::ToggleReview
+                | Effect::ToggleAgenda
+                | Effect::SetFocusMode(_)
+                | Effect::OpenGoTo(_)
+                | Effect::DraftGoTo(_)
+                | Effect::SaveNow
+                // Measurements are saved, but they're remeasured whenever they go missing
+                | Effect::InsertCalculated(..)
+        )
+    }
+
+    /// Whether the effect edits todos in a way the user can undo.
+    fn is_undoable(&self) -> bool {
+        matches!(
+            self,
+            Effect::Batch(_)
+                | Effect::AddTodo(_)
+                | Effect::ImportStickies(_)
+                | Effect::ImportBoard(_)
+                | Effect::PromoteToBoard(..)
+                | Effect::SaveTodo(..)
+                | Effect::SaveBack(..)
+                | Effect::CheckTodo(_)
+                | Effect::DeleteTodo(_)
+                | Effect::ExportSingle(..)
+                | Effect::SetColor(..)
+                | Effect::MoveToBoard(..)
+                | Effect::ResizeTodo(..)
+                | Effect::SetRecurrence(..)
+                | Effect::SetEffort(..)
+                | Effect::SetCodeLang(..)
+                | Effect::LinkTodos(..)
+                | Effect::UnlinkTodos(..)
+                | Effect::SetAlarm(..)
+                | Effect::SetReminders(..)
+                | Effect::DismissReminders(_)
+                | Effect::SnoozeTodo(..)
+                | Effect::DuplicateTodo(_)
+                | Effect::ToggleArchived(_)
+                | Effect::TogglePinned(_)
+                | Effect::ToggleHidden(_)
+                | Effect::AttachFile(..)
+                | Effect::AddSubtask(..)
+                | Effect::SaveSubtask(..)
+                | Effect::CheckSubtask(..)
+                | Effect::CheckAllSubtasks(_)
+                | Effect::DeleteSubtask(..)
+                | Effect::Reorder(..)
+                | Effect::MoveUp(_)
+                | Effect::MoveDown(_)
+        )
+    }
+}
+
+/// How a linked sticky is named in chips and menus, without giving away a hidden label.
+fn link_title(todo: &Todo) -> String {
+    if todo.is_concealed() {
+        return "Hidden sticky".to_owned();
+    }
+    let mut title: String = todo.label.chars().take(24).collect();
+    if title.len() < todo.label.len() {
+        title.push('…');
+    }
+    title
+}
+
+/// A row of swatches for the preset sticky colors, outlining `selected`. `on_pick` is called
+/// with the color clicked.
+fn color_swatch_row(
+    ui: &mut egui::Ui,
+    selected: Option<egui::Color32>,
+    mut on_pick: impl FnMut(&mut egui::Ui, egui::Color32),
+) {
+    ui.horizontal(|ui| {
+        for (name, color) in STICKY_COLORS {
+            let (rect, response) =
+                ui.allocate_exact_size(egui::Vec2::splat(18.0), egui::Sense::click());
+            let stroke = if selected == Some(color) {
+                ui.visuals().selection.stroke
+            } else {
+                ui.visuals().widgets.noninteractive.bg_stroke
+            };
+            ui.painter().rect(rect, 4.0, color, stroke);
+
+            if response.on_hover_text(name).clicked() {
+                on_pick(ui, color);
+            }
+        }
+    });
+}
+
+/// The hover text of the undo or redo button: its shortcut and how many steps it can take.
+fn history_hover(ui: &egui::Ui, shortcut: &egui::KeyboardShortcut, steps: usize) -> String {
+    let shortcut = ui.ctx().format_shortcut(shortcut);
+    let plural = if steps == 1 { "" } else { "s" };
+    format!("{shortcut} ({steps} step{plural})")
+}
+
+/// The todos and boards as they were before an undoable effect.
+struct UndoStep {
+    todos: Vec<Todo>,
+    /// Kept along with the todos, since promoting a sticky or importing a board adds a board.
+    boards: Vec<Board>,
+    active_board: usize,
+    /// The todo whose label or notes were being typed into, so later keystrokes join this step.
+    label_edit: Option<usize>,
+}
+
+/// Saved state that didn't parse as a whole, kept so it can be backed up.
+struct Recovery {
+    original: String,
+    /// Where the original was written on disk, if that worked.
+    #[cfg(not(target_arch = "wasm32"))]
+    backup_path: Option<PathBuf>,
+    recovered_todos: usize,
+    dismissed: bool,
+}
+
+/// A labeled background frame on the canvas for grouping stickies spatially.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+struct Zone {
+    name: String,
+    rect: egui::Rect,
+    color: egui::Color32,
+}
+
+impl Zone {
+    fn new(rect: egui::Rect) -> Self {
+        Self {
+            name: "New zone".to_owned(),
+            rect,
+            color: egui::Color32::from_rgb(54, 162, 235),
+        }
+    }
+}
+
+/// A separate canvas of stickies. Todos point at their board by index.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+struct Board {
+    name: String,
+    #[serde(default)]
+    sort_mode: SortMode,
+    /// The color new stickies on the board start with; `None` follows the theme.
+    #[serde(default)]
+    default_color: Option<egui::Color32>,
+    /// Whether checking off stickies here skips the celebration, like on a board for focus.
+    #[serde(default)]
+    quiet: bool,
+}
+
+impl Board {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            sort_mode: SortMode::default(),
+            default_color: None,
+            quiet: false,
+        }
+    }
+}
+
+/// A board and its stickies, as exported to share it without the rest of the workspace. Written
+/// as JSON, so it can be read by other tools as well.
+#[derive(serde::Deserialize, serde::Serialize)]
+struct BoardFile {
+    board: Board,
+    stickies: Vec<BoardSticky>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct BoardSticky {
+    todo: Todo,
+    /// Where the sticky's window was, since egui keeps that rather than the todo.
+    #[serde(default)]
+    position: Option<egui::Pos2>,
+}
+
+/// The order a board's stickies are listed and paged in.
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+enum SortMode {
+    /// The order they were added in, as rearranged by dragging or Alt+Up and Alt+Down.
+    #[default]
+    Created,
+    /// The most recently changed first.
+    Updated,
+    /// Alphabetically by label.
+    Label,
+}
+
+impl SortMode {
+    const ALL: [SortMode; 3] = [SortMode::Created, SortMode::Updated, SortMode::Label];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Created => "Order added",
+            Self::Updated => "Last changed",
+            Self::Label => "Label",
+        }
+    }
+
+    fn sort(self, todos: &mut [(usize, &Todo)]) {
+        match self {
+            Self::Created => {}
+            Self::Updated => todos.sort_by_key(|(_, todo)| std::cmp::Reverse(todo.updated_at)),
+            Self::Label => todos.sort_by_cached_key(|(_, todo)| todo.label.to_lowercase()),
+        }
+    }
+}
+
+/// A set of stickies, each a label and its subtasks, to start a common workflow from.
+struct Template {
+    name: &'static str,
+    stickies: &'static [(&'static str, &'static [&'static str])],
+}
+
+impl Template {
+    /// The effects that add this template's stickies, when the first would land at `first_index`,
+    /// with placeholders filled in as of `now`.
+    fn effects(&self, first_index: usize, now: NaiveDateTime) -> Vec<Effect> {
+        let mut effects = vec![];
+        for (offset, (label, subtasks)) in self.stickies.iter().enumerate() {
+            let index = first_index + offset;
+            effects.push(Effect::AddTodo(expand_placeholders(label, now)));
+            for (node, subtask) in subtasks.iter().enumerate() {
+                effects.push(Effect::AddSubtask(index, None));
+                effects.push(Effect::SaveSubtask(
+                    index,
+                    SubtaskPath::node(node),
+                    expand_placeholders(subtask, now),
+                ));
+            }
+        }
+        effects
+    }
+}
+
+/// Fills in a template's placeholders as of `now`:
+///
+/// - `{date}`: the date, like 2024-05-16
+/// - `{time}`: the time, like 09:30
+/// - `{weekday}`: the day of the week, like Thursday
+///
+/// Anything else in braces is left as written.
+fn expand_placeholders(text: &str, now: NaiveDateTime) -> String {
+    let mut expanded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        let value = match &rest[1..end] {
+            "date" => now.format("%Y-%m-%d").to_string(),
+            "time" => now.format("%H:%M").to_string(),
+            "weekday" => now.format("%A").to_string(),
+            _ => rest[..=end].to_owned(),
+        };
+        expanded.push_str(&value);
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// When an alarm goes off, relative to today, for grouping the agenda.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum AgendaGroup {
+    Today,
+    Tomorrow,
+    /// Later this week, which runs to Sunday.
+    ThisWeek,
+    Later,
+}
+
+impl AgendaGroup {
+    /// The group of an alarm on `day`. Alarms held back by quiet hours can be overdue, and
+    /// count as today's.
+    fn of(day: NaiveDate, today: NaiveDate) -> Self {
+        let days_left_in_week = 6 - i64::from(today.weekday().num_days_from_monday());
+        match (day - today).num_days() {
+            ..=0 => Self::Today,
+            1 => Self::Tomorrow,
+            days if days <= days_left_in_week => Self::ThisWeek,
+            _ => Self::Later,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Today => "Today",
+            Self::Tomorrow => "Tomorrow",
+            Self::ThisWeek => "This week",
+            Self::Later => "Later",
+        }
+    }
+}
+
+/// The draft being dragged from the top bar onto the canvas.
+struct DraftDrag(String);
+
+/// How many stickies were added and checked off on one day, for the weekly review.
+struct DayActivity {
+    day: NaiveDate,
+    added: usize,
+    completed: usize,
+}
+
+/// User preferences, edited from the Settings menu.
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct Settings {
+    celebrate_completion: bool,
+    /// Corner radius of sticky windows.
+    sticky_rounding: f32,
+    /// How strong sticky windows' drop shadows are, from 0 (flat) to 1 (the theme's shadow).
+    sticky_shadow: f32,
+    /// How opaque the app's window is behind the stickies, from `MIN_WINDOW_OPACITY` to 1.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    // web pages have their own background
+    window_opacity: f32,
+    /// The width to height ratio stickies keep when they're resized. `None` keeps any size.
+    aspect_ratio: Option<f32>,
+    /// Whether clicking anywhere on a sticky's body checks it off, not just its checkbox.
+    click_to_check: bool,
+    /// Whether each sticky's label starts with its place in the board's order, as used by Go to.
+    number_stickies: bool,
+    /// Whether stickies are drawn smaller, with tighter text and spacing, to fit more on the
+    /// board. Unlike zooming, the rest of the window stays as it is.
+    compact: bool,
+    /// Whether adding the draft takes Ctrl+Enter (Cmd+Enter on Mac) or the Save button, so a
+    /// stray Enter doesn't add a half-written sticky.
+    add_with_shortcut: bool,
+    /// Whether a sticky's checkbox and buttons are hidden until it's hovered or focused.
+    actions_on_hover: bool,
+    /// Whether a date phrase ending a new sticky's label, like "Friday 3pm", is taken off the
+    /// label and set as its alarm.
+    alarms_from_labels: bool,
+    /// Whether labels are trimmed and their first letter capitalized as they're saved.
+    tidy_labels: bool,
+    /// Whether words missing from the bundled word list are underlined in labels.
+    spellcheck: bool,
+    /// Whether stickies fade back while the pointer is elsewhere, unless focused or pinned.
+    fade_idle: bool,
+    /// Whether deleting from the canvas peels the sticky away, rather than removing it at once.
+    peel_on_delete: bool,
+    /// Maximum characters per line when exporting, before wrapping.
+    export_width: usize,
+    /// Labels longer than this many characters get a warning while they're edited.
+    label_warn_chars: usize,
+    /// The folder stickies were last exported to with "Export and delete", picked again first.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))] // no file system access on web
+    offload_dir: Option<PathBuf>,
+    /// How long checked todos stay on the board before being archived. `None` never archives.
+    auto_archive_after: Option<Duration>,
+    add_bar: AddBarPlacement,
+    checkbox_placement: CheckboxPlacement,
+    label_overflow: LabelOverflow,
+    /// How many stickies are shown at once, paging through the rest. `None` shows them all,
+    /// which can slow the app down on boards with hundreds of stickies.
+    page_size: Option<usize>,
+    /// Local times between which alarms wait, firing once the quiet hours end. The end may be
+    /// earlier than the start to run past midnight.
+    quiet_hours: Option<(NaiveTime, NaiveTime)>,
+    /// The zone times are shown, alarms go off, and repeating todos reopen in. `None` follows
+    /// the system's zone. Times are saved in UTC either way.
+    time_zone: Option<Tz>,
+    language: Lang,
+    /// The account stickies are emailed through as their alarms go off. `None` doesn't email
+    /// them.
+    #[cfg(not(target_arch = "wasm32"))]
+    email: Option<EmailSettings>,
+    /// Whether the search and completion filter stay as they were across restarts.
+    remember_filters: bool,
+    /// The board the app opens on, as an index into `AppState::boards`. `None` opens the one
+    /// last used.
+    startup_board: Option<usize>,
+    canvas_bg: CanvasBackground,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            celebrate_completion: true,
+            peel_on_delete: true,
+            fade_idle: false,
+            click_to_check: false,
+            compact: false,
+            number_stickies: false,
+            actions_on_hover: false,
+            alarms_from_labels: true,
+            tidy_labels: false,
+            spellcheck: false,
+            add_with_shortcut: false,
+            sticky_rounding: 6.0,
+            sticky_shadow: 1.0,
+            window_opacity: 1.0,
+            aspect_ratio: None,
+            export_width: 80,
+            offload_dir: None,
+            label_warn_chars: 120,
+            auto_archive_after: None,
+            add_bar: AddBarPlacement::default(),
+            checkbox_placement: CheckboxPlacement::default(),
+            label_overflow: LabelOverflow::default(),
+            page_size: None,
+            quiet_hours: None,
+            time_zone: None,
+            language: Lang::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            email: None,
+            remember_filters: true,
+            startup_board: None,
+            canvas_bg: CanvasBackground::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// `time` in the zone picked in Settings.
+    fn local(&self, time: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self.time_zone {
+            Some(zone) => time.with_timezone(&zone).fixed_offset(),
+            None => time.with_timezone(&Local).fixed_offset(),
+        }
+    }
+
+    /// The moment a time read in the zone picked in Settings names. `None` for a time that a
+    /// DST change skips or repeats, which is ambiguous.
+    fn utc(&self, time: NaiveDateTime) -> Option<DateTime<Utc>> {
+        let time = match self.time_zone {
+            Some(zone) => zone.from_local_datetime(&time).single()?.to_utc(),
+            None => Local.from_local_datetime(&time).single()?.to_utc(),
+        };
+        Some(time)
+    }
+}
+
+/// How a zone is named in Settings, by its IANA name like "Asia/Kolkata".
+fn time_zone_label(zone: Option<Tz>) -> &'static str {
+    zone.map_or("System", |zone| zone.name())
+}
+
+/// What's painted behind the stickies.
+#[derive(Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+enum CanvasBackground {
+    /// The theme's panel color.
+    #[default]
+    Theme,
+    Color(egui::Color32),
+    /// A PNG, loaded again from its path on startup, under a veil of the theme's panel color
+    /// so the board stays readable over it.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))] // no file system access on web
+    Image(PathBuf),
+}
+
+/// How a sticky on the canvas shows a label too long for it.
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+enum LabelOverflow {
+    /// Wrapped, with the sticky growing taller to fit it.
+    #[default]
+    Grow,
+    /// Wrapped, scrolling within the sticky's size.
+    Scroll,
+    /// Cut to one line, with the full label shown on hover.
+    Truncate,
+}
+
+impl LabelOverflow {
+    const ALL: [LabelOverflow; 3] = [
+        LabelOverflow::Grow,
+        LabelOverflow::Scroll,
+        LabelOverflow::Truncate,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Grow => "Wrap and grow",
+            Self::Scroll => "Wrap and scroll",
+            Self::Truncate => "Cut short",
+        }
+    }
+}
+
+/// Where the "Add a sticky" bar goes.
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+enum AddBarPlacement {
+    /// Under the heading, above the stickies.
+    #[default]
+    Top,
+    /// Docked along the bottom of the window.
+    Bottom,
+}
+
+/// Where a sticky's checkbox goes.
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+enum CheckboxPlacement {
+    /// At the start of the action row, apart from the label.
+    #[default]
+    ActionRow,
+    /// In front of the label, checking it off like a list item.
+    WithLabel,
+}
+
+/// How the todos are laid out in the central panel.
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+enum ViewMode {
+    /// Each todo is a floating sticky window.
+    #[default]
+    Canvas,
+    /// Todos are compact rows in a plain vertical list.
+    List,
+    /// Todo titles down the side, with the selected one shown in full beside them.
+    Detail,
+}
+
+/// Which todos to show, by whether they're checked off.
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+enum CompletionFilter {
+    #[default]
+    All,
+    Active,
+    Completed,
+    Archived,
+}
+
+impl CompletionFilter {
+    fn matches(self, todo: &Todo) -> bool {
+        match self {
+            Self::All => !todo.archived,
+            Self::Active => !todo.checked && !todo.archived,
+            Self::Completed => todo.checked && !todo.archived,
+            Self::Archived => todo.archived,
+        }
+    }
+}
+
+/// Deletes the todos at `indices`, as one undo step. They're deleted from the highest index
+/// down, so no delete shifts a todo still to be deleted.
+fn delete_batch(indices: impl IntoIterator<Item = usize>) -> Effect {
+    let mut indices: Vec<usize> = indices.into_iter().collect();
+    indices.sort_unstable_by(|a, b| b.cmp(a));
+    Effect::Batch(indices.into_iter().map(Effect::DeleteTodo).collect())
+}
+
+/// Checks or unchecks each of `todos` as one undo step, leaving those already that way alone.
+fn check_all(todos: &[(usize, &Todo)], checked: bool) -> Effect {
+    Effect::Batch(
+        todos
+            .iter()
+            .filter(|(_, todo)| todo.checked != checked)
+            .map(|(index, _)| Effect::CheckTodo(*index))
+            .collect(),
+    )
+}
+
+fn color_all(todos: &[(usize, &Todo)], color: Option<egui::Color32>) -> Effect {
+    Effect::Batch(
+        todos
+            .iter()
+            .map(|(index, _)| Effect::SetColor(*index, color))
+            .collect(),
+    )
+}
+
+/// Adds `#tag` to the end of each label that doesn't have it yet.
+fn tag_all(todos: &[(usize, &Todo)], tag: &str) -> Effect {
+    let lowercase = tag.to_lowercase();
+    Effect::Batch(
+        todos
+            .iter()
+            .filter(|(_, todo)| todo.tags().all(|other| other != lowercase))
+            .map(|(index, todo)| Effect::SaveTodo(*index, format!("{} #{tag}", todo.label)))
+            .collect(),
+    )
+}
+
+fn move_all(todos: &[(usize, &Todo)], board: usize) -> Effect {
+    Effect::Batch(
+        todos
+            .iter()
+            .map(|(index, _)| Effect::MoveToBoard(*index, board))
+            .collect(),
+    )
+}
+
+fn delete_all(todos: &[(usize, &Todo)]) -> Effect {
+    delete_batch(todos.iter().map(|(index, _)| *index))
+}
+
+/// `label` trimmed, with its first letter capitalized. Letters without a single-letter
+/// capital, as in scripts without case or like "ß", are left as they are.
+fn tidy_label(label: &str) -> String {
+    let label = label.trim();
+    let mut chars = label.chars();
+    let Some(first) = chars.next() else {
+        return String::new();
+    };
+    let mut upper = first.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(capital), None) => format!("{capital}{}", chars.as_str()),
+        _ => label.to_owned(),
+    }
+}
+
+/// `size` with its height changed to keep its width at `ratio` of it, if there's a ratio.
+fn locked_size(size: egui::Vec2, ratio: Option<f32>) -> egui::Vec2 {
+    match ratio {
+        Some(ratio) => egui::vec2(size.x, size.x / ratio),
+        None => size,
+    }
+}
+
+/// The label as shown, after its `number` in the board's order when stickies are numbered.
+fn numbered(label: &str, number: Option<usize>) -> String {
+    match number {
+        Some(number) => format!("{number}. {label}"),
+        None => label.to_owned(),
+    }
+}
+
+/// Shrinks text and spacing for compact view, by `COMPACT_SCALE`.
+fn compact_style(style: &mut egui::Style) {
+    for font in style.text_styles.values_mut() {
+        font.size *= COMPACT_SCALE;
+    }
+    let spacing = &mut style.spacing;
+    spacing.item_spacing *= COMPACT_SCALE;
+    spacing.button_padding *= COMPACT_SCALE;
+    spacing.interact_size *= COMPACT_SCALE;
+    spacing.icon_width *= COMPACT_SCALE;
+    spacing.icon_width_inner *= COMPACT_SCALE;
+}
+
+/// Picks black or white text, whichever reads better on `background`.
+fn contrasting_text_color(background: egui::Color32) -> egui::Color32 {
+    // Relative luminance as defined by WCAG, computed from linear RGB
+    let linear = egui::Rgba::from(background);
+    let luminance = 0.2126 * linear.r() + 0.7152 * linear.g() + 0.0722 * linear.b();
+
+    // The luminance at which black and white text have equal contrast ratios
+    if luminance > 0.179 {
+        egui::Color32::BLACK
+    } else {
+        egui::Color32::WHITE
+    }
+}
+
+/// Formats an elapsed duration as a short, human-friendly string like "2h ago".
+fn format_relative_time(elapsed: chrono::Duration) -> String {
+    if elapsed.num_seconds() < 10 {
+        "just now".to_owned()
+    } else {
+        format!("{} ago", format_short_duration(elapsed))
+    }
+}
+
+/// How long until `format_relative_time(elapsed)` shows something different.
+fn label_refresh_in(elapsed: chrono::Duration) -> chrono::Duration {
+    let seconds = elapsed.num_seconds().max(0);
+    let next = match seconds {
+        0..=9 => 10,
+        10..=59 => seconds + 1,
+        60..=3599 => (seconds / 60 + 1) * 60,
+        3600..=86399 => (seconds / 3600 + 1) * 3600,
+        _ => (seconds / 86400 + 1) * 86400,
+    };
+    chrono::Duration::seconds(next) - elapsed
+}
+
+/// Formats a duration in its largest whole unit, e.g. "5m" or "2d".
+fn format_short_duration(duration: chrono::Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    match seconds {
+        0..=59 => format!("{seconds}s"),
+        60..=3599 => format!("{}m", seconds / 60),
+        3600..=86399 => format!("{}h", seconds / 3600),
+        _ => format!("{}d", seconds / 86400),
+    }
+}
+
+/// Hands out todo ids from a counter persisted in egui's memory.
+fn next_todo_id(ctx: &egui::Context) -> egui::Id {
+    ctx.memory_mut(|mem| {
+        let counter: &mut usize = mem
+            .data
+            .get_persisted_mut_or_default(egui::Id::new("counter"));
+        let id = egui::Id::new(*counter);
+        *counter += 1;
+        id
+    })
+}
+
+fn archive_delay_label(delay: Option<Duration>) -> String {
+    match delay.and_then(|delay| chrono::Duration::from_std(delay).ok()) {
+        Some(delay) => format_short_duration(delay),
+        None => "Never".to_owned(),
+    }
+}
+
+/// How many open stickies, on any board, have an alarm set for `now`'s day or earlier.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))] // counted in the desktop window's title
+fn due_today(todos: &[Todo], now: DateTime<Utc>, settings: &Settings) -> usize {
+    let today = settings.local(now).date_naive();
+    todos
+        .iter()
+        .filter(|todo| !todo.checked && !todo.archived)
+        .filter_map(|todo| todo.alarm)
+        .filter(|alarm| settings.local(*alarm).date_naive() <= today)
+        .count()
+}
+
+/// The activity on each of the seven days up to and including `today`, oldest first. Only the
+/// latest change to a todo is recorded, so a checked todo counts as completed on that day.
+fn weekly_activity(todos: &[Todo], today: NaiveDate, settings: &Settings) -> Vec<DayActivity> {
+    let mut week: Vec<DayActivity> = (0..7)
+        .rev()
+        .map(|days_ago| DayActivity {
+            day: today - chrono::Duration::days(days_ago),
+            added: 0,
+            completed: 0,
+        })
+        .collect();
+
+    for todo in todos {
+        let created = settings.local(todo.created_at).date_naive();
+        if let Some(day) = week.iter_mut().find(|day| day.day == created) {
+            day.added += 1;
+        }
+
+        let updated = settings.local(todo.updated_at).date_naive();
+        if todo.checked {
+            if let Some(day) = week.iter_mut().find(|day| day.day == updated) {
+                day.completed += 1;
+            }
+        }
+    }
+    week
+}
+
+/// Looks up a preset color by name, also taking the names macOS Stickies uses.
+fn sticky_color_named(name: &str) -> Option<egui::Color32> {
+    let name = match name.to_lowercase().as_str() {
+        "gray" | "grey" => "charcoal".to_owned(),
+        name => name.to_owned(),
+    };
+    STICKY_COLORS
+        .iter()
+        .find(|(preset, _)| preset.to_lowercase() == name)
+        .map(|(_, color)| *color)
+}
+
+/// Quiet hours when first turned on, overnight from 10pm to 8am.
+fn default_quiet_hours() -> (NaiveTime, NaiveTime) {
+    let at = |hour| NaiveTime::from_hms_opt(hour, 0, 0).unwrap_or_default();
+    (at(22), at(8))
+}
+
+/// Whether `time` falls in the quiet hours from `start` up to `end`, which may wrap past midnight.
+fn in_quiet_hours((start, end): (NaiveTime, NaiveTime), time: NaiveTime) -> bool {
+    if start <= end {
+        start <= time && time < end
+    } else {
+        start <= time || time < end
+    }
+}
+
+fn page_size_label(page_size: Option<usize>) -> String {
+    match page_size {
+        Some(page_size) => page_size.to_string(),
+        None => "All".to_owned(),
+    }
+}
+
+/// The slice of `total` items on `page`, falling back to the last page when items have gone.
+fn page_range(total: usize, page_size: Option<usize>, page: usize) -> std::ops::Range<usize> {
+    let Some(page_size) = page_size.filter(|&page_size| page_size > 0) else {
+        return 0..total;
+    };
+
+    let last_page = total.saturating_sub(1) / page_size;
+    let start = page.min(last_page) * page_size;
+    start..total.min(start + page_size)
+}
+
+/// Finds the nudge that lines `rect`'s edges up with the nearest edges of `others` within
+/// `SNAP_DISTANCE`, along with the guide lines to draw for each alignment once nudged.
+fn alignment_snap(rect: egui::Rect, others: &[egui::Rect]) -> (egui::Vec2, Vec<[egui::Pos2; 2]>) {
+    let nearest = |edges: [f32; 2], targets: Vec<f32>| {
+        targets
+            .into_iter()
+            .flat_map(|target| edges.map(|edge| target - edge))
+            .filter(|delta| delta.abs() <= SNAP_DISTANCE)
+            .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+            .unwrap_or(0.0)
+    };
+    let offset = egui::vec2(
+        nearest(
+            [rect.left(), rect.right()],
+            others.iter().flat_map(|r| [r.left(), r.right()]).collect(),
+        ),
+        nearest(
+            [rect.top(), rect.bottom()],
+            others.iter().flat_map(|r| [r.top(), r.bottom()]).collect(),
+        ),
+    );
+    let snapped = rect.translate(offset);
+    let aligned = |a: f32, b: f32| (a - b).abs() < 0.5;
+
+    let mut guides = vec![];
+    for other in others {
+        for x in [other.left(), other.right()] {
+            if aligned(x, snapped.left()) || aligned(x, snapped.right()) {
+                let top = snapped.top().min(other.top());
+                let bottom = snapped.bottom().max(other.bottom());
+                guides.push([egui::pos2(x, top), egui::pos2(x, bottom)]);
+            }
+        }
+        for y in [other.top(), other.bottom()] {
+            if aligned(y, snapped.top()) || aligned(y, snapped.bottom()) {
+                let left = snapped.left().min(other.left());
+                let right = snapped.right().max(other.right());
+                guides.push([egui::pos2(left, y), egui::pos2(right, y)]);
+            }
+        }
+    }
+
+    (offset, guides)
+}
+
+/// Salvages the parts of `AppState` that hold todos, dropping only the broken todos.
+fn salvage_saved_field(name: &str, value: &str) -> Option<String> {
+    let salvage_todos = |value: &str| ron::to_string(&recovery::lenient_list::<Todo>(value)?).ok();
+    match name {
+        "core" => {
+            let core: Core = recovery::lenient_struct(value, &|name, value| {
+                (name == "todos").then(|| salvage_todos(value))?
+            })?;
+            ron::to_string(&core).ok()
+        }
+        // Saved before `core` existed
+        "todos" => salvage_todos(value),
+        _ => None,
+    }
+}
+
+/// Writes unreadable saved state to a file beside eframe's storage, returning its path.
+#[cfg(not(target_arch = "wasm32"))]
+fn backup_unreadable_state(text: &str) -> Option<PathBuf> {
+    let dir = eframe::storage_dir("stickies").unwrap_or_else(std::env::temp_dir);
+    let file_name = format!(
+        "unreadable-state-{}.ron",
+        Utc::now().format("%Y%m%d-%H%M%S")
+    );
+    let path = dir.join(file_name);
+    match std::fs::create_dir_all(&dir).and_then(|()| std::fs::write(&path, text)) {
+        Ok(()) => Some(path),
+        Err(err) => {
+            log::error!(
+                "Failed to back up unreadable state to {}: {err}",
+                path.display()
+            );
+            None
+        }
+    }
+}
+
+/// Tells monitors apart by their size in pixels and scale, since egui has no monitor ids.
+fn monitor_key(viewport: &egui::ViewportInfo) -> Option<String> {
+    let scale = viewport.native_pixels_per_point?;
+    let size = viewport.monitor_size? * scale;
+    Some(format!("{}x{}@{scale}", size.x.round(), size.y.round()))
+}
+
+/// Reads a present field as `Some`, so legacy fields can be told apart from missing ones.
+fn deserialize_some<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+fn search_id() -> egui::Id {
+    egui::Id::new("search")
+}
+
+/// The "Add a sticky" box's id, for focusing it from outside the add bar.
+fn draft_id() -> egui::Id {
+    egui::Id::new("draft")
+}
+
+fn go_to_id() -> egui::Id {
+    egui::Id::new("go_to")
+}
+
+/// The id of the field editing the label of the sticky `todo` on the canvas.
+fn label_edit_id(todo: egui::Id) -> egui::Id {
+    todo.with("label")
+}
+
+/// The id of the handle the sticky `todo` is dragged by, for telling from outside its window
+/// when it's being dragged.
+fn drag_handle_id(todo: egui::Id) -> egui::Id {
+    todo.with("handle")
+}
+
+/// Whether to ignore persisted state on startup, requested by launching with `--fresh`.
+#[cfg(not(target_arch = "wasm32"))]
+fn start_fresh(_cc: &eframe::CreationContext<'_>) -> bool {
+    std::env::args().skip(1).any(|arg| arg == "--fresh")
+}
+
+/// The storage file passed with `--storage <file>`, for keeping stickies in e.g. a synced folder.
+/// It's watched for changes made elsewhere, unlike eframe's default storage.
+#[cfg(not(target_arch = "wasm32"))]
+fn storage_path_arg() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    args.find(|arg| arg == "--storage")?;
+    args.next().map(PathBuf::from)
+}
+
+/// The sticky labels given on the command line, one per argument, as in
+/// `stickies "Feed doge" "Walk doge"`. Options like `--fresh` are skipped; labels starting with
+/// `--` can follow a lone `--`.
+#[cfg(not(target_arch = "wasm32"))]
+fn label_args(mut args: impl Iterator<Item = String>) -> Vec<String> {
+    let mut labels = vec![];
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--" => {
+                labels.extend(args);
+                break;
+            }
+            "--storage" => {
+                args.next();
+            }
+            _ if arg.starts_with("--") => {}
+            _ => labels.push(arg),
+        }
+    }
+    labels.retain(|label| !label.trim().is_empty());
+    labels
+}
+
+/// Whether to ignore persisted state on startup, requested with a `?fresh` query parameter.
+#[cfg(target_arch = "wasm32")]
+fn start_fresh(cc: &eframe::CreationContext<'_>) -> bool {
+    let location = &cc.integration_info.web_info.location;
+    location.query_map.contains_key("fresh")
+}
+
+/// Renders todos as a Markdown task list, wrapping labels to `width` characters.
+fn todos_to_markdown(todos: &[Todo], width: usize) -> String {
+    let mut markdown = "# Stickies\n\n".to_owned();
+    for todo in todos {
+        push_markdown_item(&mut markdown, 0, todo.checked, &todo.label, width);
+        for node in &todo.subtasks {
+            push_markdown_item(&mut markdown, 1, node.checked, &node.label, width);
+            for child in &node.children {
+                push_markdown_item(&mut markdown, 2, child.checked, &child.label, width);
+            }
+        }
+    }
+    markdown
+}
+
+/// Appends one task list item, nested `depth` levels deep.
+fn push_markdown_item(
+    markdown: &mut String,
+    depth: usize,
+    checked: bool,
+    label: &str,
+    width: usize,
+) {
+    let marker = if checked { "- [x] " } else { "- [ ] " };
+    let nesting = "  ".repeat(depth);
+    // Indent continuation lines so they stay part of the list item
+    let indent = " ".repeat(nesting.len() + marker.len());
+    let width = width
+        .max(MIN_EXPORT_WIDTH)
+        .saturating_sub(indent.len())
+        .max(1);
+
+    for (i, line) in wrap_words(label, width).iter().enumerate() {
+        if i == 0 {
+            markdown.push_str(&nesting);
+            markdown.push_str(marker);
+        } else {
+            markdown.push_str(&indent);
+        }
+        markdown.push_str(line);
+        markdown.push('\n');
+    }
+}
+
+/// A file in `dir` to export a sticky labelled `label` to, named after the label and numbered
+/// to keep clear of earlier exports.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))] // no file system access on web
+fn offload_path(dir: &std::path::Path, label: &str) -> PathBuf {
+    let stem = file_stem(label);
+    let mut path = dir.join(format!("{stem}.md"));
+    let mut number = 2;
+    while path.exists() {
+        path = dir.join(format!("{stem}-{number}.md"));
+        number += 1;
+    }
+    path
+}
+
+/// `label` as a file name, lowercased and dashed, without characters file systems might reject.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))] // no file system access on web
+fn file_stem(label: &str) -> String {
+    let words: Vec<String> = label
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let stem: String = words.join("-").chars().take(48).collect();
+    let stem = stem.trim_end_matches('-');
+    if stem.is_empty() {
+        "sticky".to_owned()
+    } else {
+        stem.to_owned()
+    }
+}
+
+/// Greedily wraps `text` into lines of at most `width` characters, never splitting a word.
+/// Words longer than `width` get a line to themselves.
+fn wrap_words(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+impl eframe::App for AppState {
+    /// Called by the frame work to save state before shutdown.
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        // While locked, the state is an empty placeholder that mustn't overwrite the real one
+        if self.locked.is_some() {
+            return;
+        }
+
+        // eframe keeps using its own storage for egui's memory, like window positions
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(mut custom_storage) = self.custom_storage.take() {
+            // Saving would overwrite the changes, so wait for the user to pick
+            if self.storage_changed || custom_storage.changed_on_disk() {
+                log::warn!(
+                    "Not saving over changes to {} made elsewhere",
+                    custom_storage.path().display()
+                );
+                self.storage_changed = true;
+                self.custom_storage = Some(custom_storage);
+                return;
+            }
+            if self.save_to(&mut custom_storage) {
+                self.unsaved = false;
+                self.unsaved_labels.clear();
+            }
+            eframe::Storage::flush(&mut custom_storage);
+            self.custom_storage = Some(custom_storage);
+            return;
+        }
+
+        if self.save_to(storage) {
+            self.unsaved = false;
+            self.unsaved_labels.clear();
+        }
+    }
+
+    /// Left clear so that, with `Settings::window_opacity`, only the see-through panels are drawn
+    /// over the desktop.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+        egui::Rgba::TRANSPARENT.to_array()
+    }
+
+    /// Called each time the UI needs repainting, which may be many times per second.
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        if !self.session_restored {
+            self.restore_session();
+        }
+        self.track_monitor_zoom(ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.load_canvas_texture(ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.watch_storage();
+        #[cfg(not(target_arch = "wasm32"))]
+        self.apply_window_opacity(ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.update_window_title(ctx);
+        #[cfg(any(windows, target_os = "macos"))]
+        self.update_tray(ctx);
+        if self.locked.is_none() {
+            for label in std::mem::take(&mut self.launch_labels) {
+                self.send(Effect::AddTodo(label));
+            }
+        }
+        self.render(ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.save_screenshot(ctx);
+        self.session_restored = true;
+        // Focused as it was shown, and left for the user to move away from
+        self.new_subtask = None;
+        self.apply_effects(ctx);
+
+        if std::mem::take(&mut self.save_requested) {
+            if let Some(storage) = frame.storage_mut() {
+                self.save(storage);
+                storage.flush();
+            }
+        }
+
+        if let Some(delay) = self.settings.auto_archive_after {
+            self.core.archive_expired(delay, Utc::now());
+        }
+        self.core.reopen_due(Utc::now());
+        self.core.wake_snoozed(Utc::now());
+        // Due alarms stay set through quiet hours, so they all go off once it's over
+        let quiet = self
+            .settings
+            .quiet_hours
+            .is_some_and(|hours| in_quiet_hours(hours, self.settings.local(Utc::now()).time()));
+        if !quiet {
+            for id in self.core.take_due_alarms(Utc::now()) {
+                #[cfg(not(target_arch = "wasm32"))]
+                self.email_reminder(ctx, id);
+                self.apply_effect(ctx, Effect::PopTodo(id));
+            }
+        }
+
+        // Otherwise egui only repaints on input, which keeps the app idle between events
+        if let Some(wake) = self
+            .next_wake(Utc::now())
+            .and_then(|wake| wake.to_std().ok())
+        {
+            ctx.request_repaint_after(wake);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_after_channel_closed_does_not_panic() {
+        let mut state = AppState::default();
+
+        // Replacing the receiver drops the original, closing the channel
+        let (_, effects_rx) = mpsc::channel();
+        state.effects_rx = effects_rx;
+
+        state.send(Effect::AddTodo("Feed doge".to_owned()));
+    }
+
+    #[test]
+    fn migrate_moves_top_level_todos_into_core() {
+        let todo = ron::to_string(&Todo::new(egui::Id::new(0), 0, "Feed doge".to_owned())).unwrap();
+        let saved = format!("(draft: \"half typed\", todos: [{todo}])");
+
+        let state = ron::from_str::<AppState>(&saved).unwrap().migrate();
+        assert_eq!(state.core.draft, "half typed");
+        assert_eq!(state.core.todos.len(), 1);
+        assert_eq!(state.core.todos[0].label, "Feed doge");
+    }
+
+    #[test]
+    fn no_sticky_loads_in_edit_mode() {
+        let mut state = AppState::default();
+        state.core.todos = vec![Todo::new(egui::Id::new(0), 0, "Feed doge".to_owned())];
+        state.core.todos[0].edit_mode = true;
+        let saved = ron::to_string(&state).unwrap();
+        assert!(!AppState::parse_saved(&saved).core.todos[0].edit_mode);
+
+        // Saved before edit mode stopped being saved
+        let old = saved.replace("checked:false", "checked:false,edit_mode:true");
+        assert_ne!(old, saved);
+        assert!(!AppState::parse_saved(&old).core.todos[0].edit_mode);
+    }
+
+    #[test]
+    fn new_stickies_take_the_board_color() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        state.send(Effect::AddTodo("Feed doge".to_owned()));
+        state.apply_effects(&ctx);
+        state.send(Effect::SetBoardColor(0, Some(egui::Color32::LIGHT_BLUE)));
+        state.send(Effect::AddTodo("Walk doge".to_owned()));
+        state.apply_effects(&ctx);
+
+        // Only stickies added since are colored
+        assert_eq!(state.core.todos[0].color, None);
+        assert_eq!(state.core.todos[1].color, Some(egui::Color32::LIGHT_BLUE));
+    }
+
+    #[test]
+    fn resizing_keeps_the_locked_aspect_ratio() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        state.send(Effect::AddTodo("Feed doge".to_owned()));
+        state.apply_effects(&ctx);
+
+        state.send(Effect::ResizeTodo(0, egui::vec2(200.0, 120.0)));
+        state.apply_effects(&ctx);
+        assert_eq!(state.core.todos[0].size, egui::vec2(200.0, 120.0));
+
+        state.settings.aspect_ratio = Some(4.0 / 3.0);
+        state.send(Effect::ResizeTodo(0, egui::vec2(200.0, 120.0)));
+        state.apply_effects(&ctx);
+        assert_eq!(state.core.todos[0].size, egui::vec2(200.0, 150.0));
+    }
+
+    #[test]
+    fn tidies_labels_only_when_set() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        state.send(Effect::AddTodo("  feed doge ".to_owned()));
+        state.apply_effects(&ctx);
+        assert_eq!(state.core.todos[0].label, "  feed doge ");
+
+        state.settings.tidy_labels = true;
+        state.send(Effect::EditTodo(0));
+        state.send(Effect::SaveTodo(0, " feed doge\n".to_owned()));
+        state.apply_effects(&ctx);
+        assert_eq!(state.core.todos[0].label, " feed doge\n");
+        state.send(Effect::EditTodo(0));
+        state.apply_effects(&ctx);
+        assert_eq!(state.core.todos[0].label, "Feed doge");
+
+        assert_eq!(tidy_label("émile"), "Émile");
+        assert_eq!(tidy_label("ßtraße"), "ßtraße");
+        assert_eq!(tidy_label(" 猫に餌 "), "猫に餌");
+    }
+
+    #[test]
+    fn shortcuts_work_with_chrome_hidden() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        state.send(Effect::AddTodo("Feed doge".to_owned()));
+        state.apply_effects(&ctx);
+
+        run_frame(
+            &ctx,
+            &mut state,
+            vec![key(egui::Key::F11, egui::Modifiers::NONE)],
+        );
+        assert!(state.chrome_hidden);
+
+        run_frame(
+            &ctx,
+            &mut state,
+            vec![key(egui::Key::Z, egui::Modifiers::COMMAND)],
+        );
+        assert!(state.core.todos.is_empty());
+
+        run_frame(
+            &ctx,
+            &mut state,
+            vec![key(egui::Key::Escape, egui::Modifiers::NONE)],
+        );
+        assert!(!state.chrome_hidden);
+    }
+
+    #[test]
+    fn opens_on_the_startup_board_or_else_the_first() {
+        let mut state = AppState {
+            boards: vec![
+                Board::new("Home".to_owned()),
+                Board::new("Today".to_owned()),
+            ],
+            ..AppState::default()
+        };
+        state.settings.startup_board = Some(1);
+        let saved = ron::to_string(&state).unwrap();
+        assert_eq!(AppState::parse_saved(&saved).active_board, 1);
+
+        state.active_board = 1;
+        state.settings.startup_board = Some(5);
+        let saved = ron::to_string(&state).unwrap();
+        assert_eq!(AppState::parse_saved(&saved).active_board, 0);
+    }
+
+    #[test]
+    fn label_args_skip_options_and_blanks() {
+        let args = [
+            "--storage",
+            "s.ron",
+            "Feed doge",
+            "--fresh",
+            " ",
+            "--",
+            "--walk",
+        ];
+
+        let labels = label_args(args.into_iter().map(str::to_owned));
+        assert_eq!(labels, ["Feed doge", "--walk"]);
+    }
+
+    #[test]
+    fn exported_stickies_leave_the_board_only_once_written() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        state.send(Effect::AddTodo("Feed doge".to_owned()));
+        state.apply_effects(&ctx);
+
+        let missing = std::env::temp_dir().join("stickies-missing-offload-test/nested");
+        state.send(Effect::ExportSingle(0, missing));
+        state.apply_effects(&ctx);
+        assert_eq!(state.core.todos.len(), 1);
+        assert!(state.export_error.is_some());
+
+        let dir = std::env::temp_dir().join("stickies-offload-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        state.send(Effect::ExportSingle(0, dir.clone()));
+        state.apply_effects(&ctx);
+        let text = std::fs::read_to_string(dir.join("feed-doge.md")).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert!(text.contains("Feed doge"));
+        assert!(state.core.todos.is_empty());
+
+        state.send(Effect::Undo);
+        state.apply_effects(&ctx);
+        assert_eq!(state.core.todos.len(), 1);
+    }
+
+    #[test]
+    fn exported_boards_import_as_new_boards() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        state.send(Effect::AddTodo("Feed doge".to_owned()));
+        state.apply_effects(&ctx);
+
+        let path = std::env::temp_dir().join("stickies-exported-board-test.json");
+        state.send(Effect::ExportBoard(0, path.clone()));
+        state.send(Effect::ImportBoard(path.clone()));
+        state.apply_effects(&ctx);
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert!(serde_json::from_str::<serde_json::Value>(&text).is_ok());
+        assert_eq!(state.boards.len(), 2);
+        assert_eq!(state.active_board, 1);
+        let imported: Vec<&str> = state
+            .core
+            .todos
+            .iter()
+            .filter(|todo| todo.board == 1)
+            .map(|todo| todo.label.as_str())
+            .collect();
+        assert_eq!(imported, ["Feed doge"]);
+        assert_ne!(state.core.todos[0].id, state.core.todos[1].id);
+
+        state.send(Effect::Undo);
+        state.apply_effects(&ctx);
+        assert_eq!(state.boards.len(), 1);
+        assert_eq!(state.active_board, 0);
+        assert_eq!(state.core.todos.len(), 1);
+    }
+
+    #[test]
+    fn boards_saved_before_sorting_keep_added_order() {
+        let state = ron::from_str::<AppState>("(boards: [(name: \"Home\")])").unwrap();
+        assert!(state.boards[0].sort_mode == SortMode::Created);
+    }
+
+    #[test]
+    fn salvage_keeps_readable_todos() {
+        let todo = |label: &str| {
+            ron::to_string(&Todo::new(egui::Id::new(label), 0, label.to_owned())).unwrap()
+        };
+        let broken = todo("broken").replace("checked:false", "checked:\"nope\"");
+        let saved = format!(
+            "(core: (todos: [{}, {broken}]), view: List, zones: \"unreadable\")",
+            todo("kept")
+        );
+        assert!(ron::from_str::<AppState>(&saved).is_err());
+
+        let state: AppState = recovery::lenient_struct(&saved, &salvage_saved_field).unwrap();
+        assert_eq!(state.core.todos.len(), 1);
+        assert_eq!(state.core.todos[0].label, "kept");
+        assert!(state.view == ViewMode::List);
+    }
+
+    /// Renders one frame with `events` as the input, then applies the resulting effects.
+    fn run_frame(ctx: &egui::Context, state: &mut AppState, events: Vec<egui::Event>) {
+        let input = egui::RawInput {
+            events,
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |ctx| {
+            state.render(ctx);
+            state.apply_effects(ctx);
+        });
+    }
+
+    /// A press of `key` with `modifiers` held, as input for `run_frame`.
+    fn key(key: egui::Key, modifiers: egui::Modifiers) -> egui::Event {
+        egui::Event::Key {
+            key,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers,
+        }
+    }
+
+    #[test]
+    fn idle_frames_send_no_effects() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        state.send(Effect::AddTodo("Feed doge".to_owned()));
+        state.apply_effects(&ctx);
+        state.send(Effect::EditTodo(0));
+        state.apply_effects(&ctx);
+
+        // The first frames measure widgets, which is sent as effects
+        for _ in 0..3 {
+            run_frame(&ctx, &mut state, vec![]);
+        }
+        let _ = ctx.run(egui::RawInput::default(), |ctx| state.render(ctx));
+        assert!(state.effects_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn typing_keeps_spaces_until_editing_ends() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        state.settings.tidy_labels = true;
+        state.send(Effect::AddTodo("feed".to_owned()));
+        state.apply_effects(&ctx);
+        state.send(Effect::EditTodo(0));
+        state.apply_effects(&ctx);
+        // The first frames measure widgets, and the sticky lays out around them
+        for _ in 0..3 {
+            run_frame(&ctx, &mut state, vec![]);
+        }
+        ctx.memory_mut(|mem| mem.request_focus(label_edit_id(state.core.todos[0].id)));
+        run_frame(&ctx, &mut state, vec![]);
+
+        run_frame(&ctx, &mut state, vec![egui::Event::Text(" ".to_owned())]);
+        assert_eq!(state.core.todos[0].label, "Feed ");
+        run_frame(
+            &ctx,
+            &mut state,
+            vec![egui::Event::Text("doge ".to_owned())],
+        );
+        assert_eq!(state.core.todos[0].label, "Feed doge ");
+
+        let enter = key(egui::Key::Enter, egui::Modifiers::NONE);
+        run_frame(&ctx, &mut state, vec![enter]);
+        assert_eq!(state.core.todos[0].label, "Feed doge");
+        assert!(!state.core.todos[0].edit_mode);
+    }
+
+    #[test]
+    fn space_checks_focused_sticky() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        state.send(Effect::AddTodo("Feed doge".to_owned()));
+        state.apply_effects(&ctx);
+        state.send(Effect::FocusTodo(Some(state.core.todos[0].id)));
+        state.apply_effects(&ctx);
+
+        let space = |modifiers| key(egui::Key::Space, modifiers);
+        run_frame(&ctx, &mut state, vec![space(egui::Modifiers::COMMAND)]);
+        assert!(!state.core.todos[0].checked);
+
+        run_frame(&ctx, &mut state, vec![space(egui::Modifiers::NONE)]);
+        assert!(state.core.todos[0].checked);
+
+        run_frame(&ctx, &mut state, vec![space(egui::Modifiers::NONE)]);
+        assert!(!state.core.todos[0].checked);
+    }
+
+    #[test]
+    fn tab_adds_subtask_to_focused_sticky() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        state.send(Effect::AddTodo("Feed doge".to_owned()));
+        state.apply_effects(&ctx);
+        state.send(Effect::FocusTodo(Some(state.core.todos[0].id)));
+        state.apply_effects(&ctx);
+
+        let tab = key(egui::Key::Tab, egui::Modifiers::NONE);
+        run_frame(&ctx, &mut state, vec![tab]);
+        assert_eq!(state.core.todos[0].subtasks.len(), 1);
+        assert!(state.core.todos[0].edit_mode);
+    }
+
+    #[test]
+    fn e_archives_focused_sticky() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        state.send(Effect::AddTodo("Feed doge".to_owned()));
+        state.send(Effect::AddTodo("Walk doge".to_owned()));
+        state.apply_effects(&ctx);
+        state.send(Effect::FocusTodo(Some(state.core.todos[1].id)));
+        state.apply_effects(&ctx);
+
+        let e = |modifiers| key(egui::Key::E, modifiers);
+        run_frame(&ctx, &mut state, vec![e(egui::Modifiers::COMMAND)]);
+        assert!(!state.core.todos[1].archived);
+
+        run_frame(&ctx, &mut state, vec![e(egui::Modifiers::NONE)]);
+        assert!(!state.core.todos[0].archived);
+        assert!(state.core.todos[1].archived);
+    }
+
+    #[test]
+    fn effects_apply_by_index_as_rendered() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        for label in ["Feed doge", "Walk doge", "Pet doge"] {
+            state.send(Effect::AddTodo(label.to_owned()));
+        }
+        state.apply_effects(&ctx);
+
+        // Sent in an order that would shift the later indices if applied as sent
+        state.send(Effect::DeleteTodo(0));
+        state.send(Effect::CheckTodo(2));
+        state.send(Effect::DeleteTodo(1));
+        state.apply_effects(&ctx);
+
+        let labels: Vec<_> = state.core.todos.iter().map(|t| t.label.as_str()).collect();
+        assert_eq!(labels, ["Pet doge"]);
+        assert!(state.core.todos[0].checked);
+    }
+
+    #[test]
+    fn restoring_drops_focus_on_deleted_sticky() {
+        let mut state = AppState {
+            focused_todo: Some(egui::Id::new("deleted")),
+            ..AppState::default()
+        };
+        state.restore_session();
+        assert_eq!(state.focused_todo, None);
+    }
+
+    #[test]
+    fn batch_is_one_undo_step() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+
+        let adds = (0..100)
+            .map(|i| Effect::AddTodo(format!("Todo {i}")))
+            .collect();
+        state.send(Effect::Batch(adds));
+        state.apply_effects(&ctx);
+        assert_eq!(state.core.todos.len(), 100);
+        assert_eq!(state.undo_stack.len(), 1);
+
+        state.send(Effect::Undo);
+        state.apply_effects(&ctx);
+        assert!(state.core.todos.is_empty());
+        assert!(state.undo_stack.is_empty());
+    }
+
+    #[test]
+    fn redo_restores_undone_step_until_next_change() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        state.send(Effect::AddTodo("Feed doge".to_owned()));
+        state.send(Effect::Undo);
+        state.apply_effects(&ctx);
+        assert!(state.core.todos.is_empty());
+        assert_eq!(state.redo_stack.len(), 1);
+
+        state.send(Effect::Redo);
+        state.apply_effects(&ctx);
+        assert_eq!(state.core.todos[0].label, "Feed doge");
+        assert_eq!(state.undo_stack.len(), 1);
+        assert!(state.redo_stack.is_empty());
+
+        state.send(Effect::Undo);
+        state.apply_effects(&ctx);
+        state.send(Effect::AddTodo("Walk doge".to_owned()));
+        state.apply_effects(&ctx);
+        assert!(state.redo_stack.is_empty());
+    }
+
+    #[test]
+    fn undoing_a_promotion_removes_its_board() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        state.send(Effect::AddTodo("Walk doge".to_owned()));
+        state.apply_effects(&ctx);
+        state.send(Effect::AddSubtask(0, None));
+        state.apply_effects(&ctx);
+
+        state.send(Effect::PromoteToBoard(state.core.todos[0].id, true));
+        state.apply_effects(&ctx);
+        assert_eq!(state.boards.len(), 2);
+        assert_eq!(state.active_board, 1);
+
+        state.send(Effect::Undo);
+        state.apply_effects(&ctx);
+        assert_eq!(state.boards.len(), 1);
+        assert_eq!(state.active_board, 0);
+        assert_eq!(state.core.todos.len(), 1);
+
+        state.send(Effect::Redo);
+        state.apply_effects(&ctx);
+        assert_eq!(state.boards.len(), 2);
+        assert_eq!(state.active_board, 1);
+    }
+
+    #[test]
+    fn undo_reverts_edits_that_keep_the_todo() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        state.send(Effect::AddTodo("Feed doge".to_owned()));
+        state.apply_effects(&ctx);
+
+        state.send(Effect::SetColor(0, Some(egui::Color32::LIGHT_BLUE)));
+        state.apply_effects(&ctx);
+        state.send(Effect::Undo);
+        state.apply_effects(&ctx);
+        assert_eq!(state.core.todos[0].color, None);
+
+        state.send(Effect::SaveTodo(0, "Feed doge #errand".to_owned()));
+        state.apply_effects(&ctx);
+        assert_eq!(state.core.todos[0].tags().collect::<Vec<_>>(), ["errand"]);
+        state.send(Effect::Undo);
+        state.apply_effects(&ctx);
+        assert_eq!(state.core.todos[0].tags().count(), 0);
+
+        state.send(Effect::SetEffort(0, Some(3)));
+        state.send(Effect::SetAlarm(
+            0,
+            Some(Utc::now() + chrono::Duration::hours(1)),
+        ));
+        state.apply_effects(&ctx);
+        state.send(Effect::Undo);
+        state.send(Effect::Undo);
+        state.apply_effects(&ctx);
+        assert_eq!(state.core.todos[0].effort, None);
+        assert_eq!(state.core.todos[0].alarm, None);
+
+        // Only the add is left to undo
+        assert_eq!(state.undo_stack.len(), 1);
+    }
+
+    #[test]
+    fn check_all_only_checks_the_filtered_stickies() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        for label in ["Feed doge", "Walk doge", "Call mom"] {
+            state.send(Effect::AddTodo(label.to_owned()));
+        }
+        state.apply_effects(&ctx);
+        state.send(Effect::CheckTodo(1));
+        state.send(Effect::SearchTodos("doge".to_owned()));
+        state.apply_effects(&ctx);
+
+        let shown: Vec<_> = state.visible_todos().collect();
+        state.send(check_all(&shown, true));
+        state.apply_effects(&ctx);
+        let checked: Vec<bool> = state.core.todos.iter().map(|todo| todo.checked).collect();
+        assert_eq!(checked, [true, true, false]);
+        assert_eq!(state.undo_stack.len(), 5);
+    }
+
+    #[test]
+    fn shown_actions_only_touch_the_filtered_stickies() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        state.send(Effect::AddBoard);
+        state.apply_effects(&ctx);
+        state.send(Effect::SwitchBoard(0));
+        for label in ["Feed doge #pets", "Walk doge", "Call mom"] {
+            state.send(Effect::AddTodo(label.to_owned()));
+        }
+        state.apply_effects(&ctx);
+        state.send(Effect::SearchTodos("doge".to_owned()));
+        state.apply_effects(&ctx);
+
+        let shown: Vec<_> = state.visible_todos().collect();
+        state.send(tag_all(&shown, "Pets"));
+        state.apply_effects(&ctx);
+        let labels: Vec<&str> = state.core.todos.iter().map(|t| t.label.as_str()).collect();
+        assert_eq!(labels, ["Feed doge #pets", "Walk doge #Pets", "Call mom"]);
+
+        let shown: Vec<_> = state.visible_todos().collect();
+        state.send(move_all(&shown, 1));
+        state.apply_effects(&ctx);
+        let boards: Vec<usize> = state.core.todos.iter().map(|todo| todo.board).collect();
+        assert_eq!(boards, [1, 1, 0]);
+
+        state.send(Effect::SwitchBoard(1));
+        state.apply_effects(&ctx);
+        state.send(Effect::SearchTodos("feed".to_owned()));
+        state.apply_effects(&ctx);
+        let shown: Vec<_> = state.visible_todos().collect();
+        state.send(delete_all(&shown));
+        state.apply_effects(&ctx);
+        let labels: Vec<&str> = state.core.todos.iter().map(|t| t.label.as_str()).collect();
+        assert_eq!(labels, ["Walk doge #Pets", "Call mom"]);
+    }
+
+    #[test]
+    fn deleting_shown_stickies_sorted_by_update_keeps_the_rest() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        for label in ["Feed doge", "Walk doge", "Wash doge", "Call mom"] {
+            state.send(Effect::AddTodo(label.to_owned()));
+        }
+        state.apply_effects(&ctx);
+        state.send(Effect::CheckTodo(3));
+        state.send(Effect::SetSortMode(0, SortMode::Updated));
+        state.send(Effect::SetCompletionFilter(CompletionFilter::Active));
+        state.apply_effects(&ctx);
+        let now = Utc::now();
+        for (index, minutes) in [(0, 2), (1, 3), (2, 1)] {
+            state.core.todos[index].updated_at = now + chrono::Duration::minutes(minutes);
+        }
+
+        // Most recently updated first, which puts the todos out of index order
+        let shown: Vec<usize> = state.visible_todos().map(|(index, _)| index).collect();
+        assert_eq!(shown, [1, 0, 2]);
+        state.send(delete_batch(shown));
+        state.apply_effects(&ctx);
+        let labels: Vec<&str> = state.core.todos.iter().map(|t| t.label.as_str()).collect();
+        assert_eq!(labels, ["Call mom"]);
+    }
+
+    #[test]
+    fn delete_all_deletes_the_shown_stickies_sorted_by_label() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        for label in ["b doge", "c mom", "a doge", "d doge", "e mom"] {
+            state.send(Effect::AddTodo(label.to_owned()));
+        }
+        state.apply_effects(&ctx);
+        state.send(Effect::SetSortMode(0, SortMode::Label));
+        state.send(Effect::SearchTodos("doge".to_owned()));
+        state.apply_effects(&ctx);
+
+        let shown: Vec<_> = state.visible_todos().collect();
+        assert_eq!(
+            shown.iter().map(|(index, _)| *index).collect::<Vec<_>>(),
+            [2, 0, 3]
+        );
+        state.send(delete_all(&shown));
+        state.apply_effects(&ctx);
+        let labels: Vec<&str> = state.core.todos.iter().map(|t| t.label.as_str()).collect();
+        assert_eq!(labels, ["c mom", "e mom"]);
+    }
+
+    #[test]
+    fn regex_search_matches_labels_or_nothing_if_invalid() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        for label in ["Call 555-1234", "Feed doge", "Call mom"] {
+            state.send(Effect::AddTodo(label.to_owned()));
+        }
+        state.send(Effect::ToggleSearchRegex);
+        state.send(Effect::SearchTodos(r"call \d+".to_owned()));
+        state.apply_effects(&ctx);
+        let shown: Vec<usize> = state.visible_todos().map(|(index, _)| index).collect();
+        assert_eq!(shown, [0]);
+
+        state.send(Effect::SearchTodos("call (".to_owned()));
+        state.apply_effects(&ctx);
+        assert_eq!(state.visible_todos().count(), 0);
+
+        // Off again, the same query is only text
+        state.send(Effect::ToggleSearchRegex);
+        state.send(Effect::SearchTodos("call".to_owned()));
+        state.apply_effects(&ctx);
+        assert_eq!(state.visible_todos().count(), 2);
+    }
+
+    #[test]
+    fn go_to_counts_only_visible_stickies() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        for label in ["feed doge", "walk doge", "feed cat"] {
+            state.send(Effect::AddTodo(label.to_owned()));
+        }
+        state.send(Effect::SearchTodos("feed".to_owned()));
+        state.apply_effects(&ctx);
+
+        state.send(Effect::GoTo(1));
+        state.apply_effects(&ctx);
+        assert_eq!(state.focused_todo, Some(state.core.todos[2].id));
+
+        // Out of range leaves the focus alone
+        state.send(Effect::GoTo(2));
+        state.apply_effects(&ctx);
+        assert_eq!(state.focused_todo, Some(state.core.todos[2].id));
+    }
+
+    #[test]
+    fn times_show_in_the_picked_zone_but_save_in_utc() {
+        let settings = Settings {
+            time_zone: Some(Tz::Asia__Kolkata),
+            ..Settings::default()
+        };
+        let noon = NaiveDate::from_ymd_opt(2024, 5, 16)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+
+        let alarm = settings.utc(noon).unwrap();
+        assert_eq!(
+            alarm.naive_utc().time(),
+            NaiveTime::from_hms_opt(6, 30, 0).unwrap()
+        );
+        assert_eq!(settings.local(alarm).naive_local(), noon);
+        assert_eq!(time_zone_label(settings.time_zone), "Asia/Kolkata");
+
+        // Saved with its offset, so it reads back as the same moment wherever it's loaded
+        let saved = ron::to_string(&alarm).unwrap();
+        assert!(saved.contains("06:30:00Z"));
+    }
+
+    #[test]
+    fn repeating_stickies_reopen_at_midnight_in_the_picked_zone() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        state.settings.time_zone = Some(Tz::Pacific__Kiritimati);
+        state.send(Effect::AddTodo("Feed doge".to_owned()));
+        state.apply_effects(&ctx);
+        state.send(Effect::SetRecurrence(0, Some("FREQ=DAILY".to_owned())));
+        state.send(Effect::CheckTodo(0));
+        state.apply_effects(&ctx);
+
+        let reopens_at = state.core.todos[0].reopens_at.unwrap();
+        assert_eq!(state.settings.local(reopens_at).time(), NaiveTime::MIN);
+    }
+
+    #[test]
+    fn due_today_counts_open_stickies_due_or_overdue() {
+        let now = Utc::now();
+        let settings = Settings::default();
+        let mut todos: Vec<Todo> = (0..4)
+            .map(|id| Todo::new(egui::Id::new(id), 0, "Feed doge".to_owned()))
+            .collect();
+        todos[0].alarm = Some(now - chrono::Duration::days(1));
+        todos[1].alarm = Some(now);
+        todos[2].alarm = Some(now);
+        todos[2].checked = true;
+        todos[3].alarm = Some(now + chrono::Duration::days(2));
+
+        assert_eq!(due_today(&todos, now, &settings), 2);
+    }
+
+    #[test]
+    fn weekly_activity_counts_by_day() {
+        let now = Utc::now();
+        let settings = Settings::default();
+        let today = settings.local(now).date_naive();
+        let mut todos = vec![
+            Todo::new(egui::Id::new(1), 0, "today".to_owned()),
+            Todo::new(egui::Id::new(2), 0, "last month".to_owned()),
+        ];
+        todos[0].checked = true;
+        todos[1].created_at = now - chrono::Duration::days(30);
+
+        let week = weekly_activity(&todos, today, &settings);
+        assert_eq!(week.len(), 7);
+        assert_eq!(week[6].day, today);
+        assert_eq!((week[6].added, week[6].completed), (1, 1));
+        assert_eq!(week.iter().map(|day| day.added).sum::<usize>(), 1);
+    }
+
+    #[test]
+    fn agenda_groups_by_day_and_week() {
+        // A Thursday
+        let today = NaiveDate::from_ymd_opt(2024, 5, 16).unwrap();
+        let group = |days| AgendaGroup::of(today + chrono::Duration::days(days), today);
+
+        assert_eq!(group(-1), AgendaGroup::Today);
+        assert_eq!(group(0), AgendaGroup::Today);
+        assert_eq!(group(1), AgendaGroup::Tomorrow);
+        assert_eq!(group(3), AgendaGroup::ThisWeek);
+        assert_eq!(group(4), AgendaGroup::Later);
+    }
+
+    #[test]
+    fn quiet_hours_wrap_past_midnight() {
+        let at = |hour| NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+
+        assert!(in_quiet_hours((at(22), at(8)), at(23)));
+        assert!(in_quiet_hours((at(22), at(8)), at(3)));
+        assert!(!in_quiet_hours((at(22), at(8)), at(8)));
+        assert!(in_quiet_hours((at(13), at(14)), at(13)));
+        assert!(!in_quiet_hours((at(13), at(14)), at(22)));
+    }
+
+    #[test]
+    fn templates_add_stickies_with_subtasks() {
+        let ctx = egui::Context::default();
+        let mut state = AppState::default();
+        state.send(Effect::AddTodo("Feed doge".to_owned()));
+        state.apply_effects(&ctx);
+
+        let standup = TEMPLATES
+            .iter()
+            .find(|t| t.name == "Daily standup")
+            .unwrap();
+        state.send(Effect::Batch(
+            standup.effects(state.core.todos.len(), Local::now().naive_local()),
+        ));
+        state.apply_effects(&ctx);
+
+        let added = &state.core.todos[1];
+        assert_eq!(added.label, "Standup");
+        assert_eq!(added.subtasks.len(), 3);
+        assert_eq!(added.subtasks[2].label, "Blockers");
+        assert!(state.core.todos[0].subtasks.is_empty());
+    }
+
+    #[test]
+    fn placeholders_expand_and_unknown_ones_stay() {
+        let now = NaiveDate::from_ymd_opt(2024, 5, 16)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap();
+
+        assert_eq!(
+            expand_placeholders("{weekday} {date} at {time}", now),
+            "Thursday 2024-05-16 at 09:30"
+        );
+        assert_eq!(expand_placeholders("{mood} {date", now), "{mood} {date");
+    }
+
+    #[test]
+    fn label_refresh_waits_for_the_next_unit() {
+        let refresh = |seconds| label_refresh_in(chrono::Duration::seconds(seconds)).num_seconds();
+
+        assert_eq!(refresh(3), 7);
+        assert_eq!(refresh(30), 1);
+        assert_eq!(refresh(90), 30);
+        assert_eq!(refresh(3600 + 5), 3600 - 5);
+    }
+
+    #[test]
+    fn file_stem_keeps_only_words() {
+        assert_eq!(
+            file_stem("Feed doge: kibble/cake?"),
+            "feed-doge-kibble-cake"
+        );
+        assert_eq!(file_stem("  ¿Qué?  "), "qué");
+        assert_eq!(file_stem("!!!"), "sticky");
+    }
+
+    #[test]
+    fn wrap_words_keeps_words_whole() {
+        assert_eq!(
+            wrap_words("feed the doge some kibble", 10),
+            ["feed the", "doge some", "kibble"]
+        );
+        assert_eq!(
+            wrap_words("supercalifragilistic doge", 10),
+            ["supercalifragilistic", "doge"]
+        );
+        assert_eq!(wrap_words("", 10), [""]);
+    }
+
+    #[test]
+    fn page_range_clamps_to_last_page() {
+        assert_eq!(page_range(120, Some(50), 0), 0..50);
+        assert_eq!(page_range(120, Some(50), 2), 100..120);
+        assert_eq!(page_range(120, Some(50), 7), 100..120);
+        assert_eq!(page_range(0, Some(50), 1), 0..0);
+        assert_eq!(page_range(120, None, 3), 0..120);
     }
 }
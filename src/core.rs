@@ -0,0 +1,988 @@
+//! The todos and the edits made to them, kept free of egui's context and UI so the logic can be
+//! exercised on its own. `AppState::apply_effects` dispatches to the methods here.
+
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use crate::recurrence::Recurrence;
+
+/// The size of a sticky's content, inside its window frame.
+pub(crate) const DEFAULT_STICKY_SIZE: egui::Vec2 = egui::vec2(150.0, 150.0);
+/// The most characters a label keeps; longer ones are cut short so layout stays usable.
+pub(crate) const MAX_LABEL_CHARS: usize = 500;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct Core {
+    pub(crate) draft: String,
+    pub(crate) todos: Vec<Todo>,
+}
+
+impl Default for Core {
+    fn default() -> Self {
+        Self {
+            draft: "Feed doge".to_owned(),
+            todos: vec![],
+        }
+    }
+}
+
+impl Core {
+    pub(crate) fn add_todo(&mut self, id: egui::Id, board: usize, label: String) {
+        let todo = Todo {
+            z: self.next_z(),
+            ..Todo::new(id, board, cap_label(label))
+        };
+        self.todos.push(todo);
+        self.draft.clear();
+    }
+
+    /// A `z` above every todo's, to stack a todo on top of them.
+    pub(crate) fn next_z(&self) -> u32 {
+        self.todos.iter().map(|todo| todo.z + 1).max().unwrap_or(0)
+    }
+
+    /// Stacks the todo above all the others, unless it's already alone on top.
+    pub(crate) fn raise(&mut self, index: usize) {
+        let Some(z) = self.todos.get(index).map(|todo| todo.z) else {
+            return;
+        };
+        let covered = self
+            .todos
+            .iter()
+            .enumerate()
+            .any(|(i, todo)| i != index && todo.z >= z);
+        if covered {
+            self.todos[index].z = self.next_z();
+        }
+    }
+
+    pub(crate) fn toggle_edit(&mut self, index: usize) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.edit_mode = !todo.edit_mode;
+        }
+    }
+
+    pub(crate) fn save_label(&mut self, index: usize, label: String) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            let label = cap_label(label);
+            // Editing resends the label every frame, so only count real changes as updates
+            if todo.label != label {
+                todo.label = label;
+                todo.updated_at = Utc::now();
+            }
+        }
+    }
+
+    pub(crate) fn save_back(&mut self, index: usize, back: String) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.back = back;
+            todo.updated_at = Utc::now();
+        }
+    }
+
+    pub(crate) fn flip(&mut self, index: usize) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.showing_back = !todo.showing_back;
+        }
+    }
+
+    /// Checks or unchecks the todo. A repeating one reopens at the start of its next day in
+    /// `now`'s zone.
+    pub(crate) fn check_todo<Z: TimeZone>(&mut self, index: usize, now: DateTime<Z>) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.checked = !todo.checked;
+            todo.updated_at = now.with_timezone(&Utc);
+            // A repeating todo comes back unchecked at its next occurrence
+            todo.reopens_at = match todo.recurrence() {
+                Some(recurrence) if todo.checked => {
+                    Some(recurrence.next_after(now).with_timezone(&Utc))
+                }
+                _ => None,
+            };
+            // Unchecking brings an archived todo back
+            if !todo.checked {
+                todo.archived = false;
+            }
+        }
+    }
+
+    pub(crate) fn delete_todo(&mut self, index: usize) {
+        if index < self.todos.len() {
+            let deleted = self.todos.remove(index);
+            // Links to the deleted todo would lead nowhere
+            for todo in &mut self.todos {
+                todo.links.retain(|link| *link != deleted.id);
+            }
+        }
+    }
+
+    /// Links the todo to the one with id `target`, unless it's itself or already linked.
+    pub(crate) fn link_todos(&mut self, index: usize, target: egui::Id) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            if todo.id != target && !todo.links.contains(&target) {
+                todo.links.push(target);
+            }
+        }
+    }
+
+    pub(crate) fn unlink_todos(&mut self, index: usize, target: egui::Id) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.links.retain(|link| *link != target);
+        }
+    }
+
+    pub(crate) fn set_color(&mut self, index: usize, color: Option<egui::Color32>) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.color = color;
+            todo.updated_at = Utc::now();
+        }
+    }
+
+    pub(crate) fn move_to_board(&mut self, index: usize, board: usize) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.board = board;
+            todo.updated_at = Utc::now();
+        }
+    }
+
+    pub(crate) fn resize(&mut self, index: usize, size: egui::Vec2) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.size = size;
+        }
+    }
+
+    /// Inserts a copy of the todo right after it, under a fresh `id`, opening at `position`.
+    pub(crate) fn duplicate_todo(
+        &mut self,
+        index: usize,
+        id: egui::Id,
+        position: Option<egui::Pos2>,
+    ) {
+        if let Some(todo) = self.todos.get(index) {
+            let copy = Todo {
+                id,
+                edit_mode: false,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                z: self.next_z(),
+                initial_position: position,
+                snap_position: None,
+                ..todo.clone()
+            };
+            self.todos.insert(index + 1, copy);
+        }
+    }
+
+    /// Archives or restores a todo by hand, whether or not it's checked.
+    pub(crate) fn toggle_archived(&mut self, index: usize) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.archived = !todo.archived;
+            todo.updated_at = Utc::now();
+        }
+    }
+
+    pub(crate) fn toggle_pinned(&mut self, index: usize) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.pinned = !todo.pinned;
+        }
+    }
+
+    pub(crate) fn toggle_hidden(&mut self, index: usize) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.hidden = !todo.hidden;
+            todo.revealed = false;
+        }
+    }
+
+    pub(crate) fn toggle_revealed(&mut self, index: usize) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.revealed = !todo.revealed;
+        }
+    }
+
+    pub(crate) fn attach(&mut self, index: usize, attachment: Option<PathBuf>) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.attachment = attachment;
+        }
+    }
+
+    pub(crate) fn snap_to(&mut self, index: usize, position: Option<egui::Pos2>) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.snap_position = position;
+        }
+    }
+
+    /// Adds an empty subtask, nested under the top-level subtask `parent` if given.
+    pub(crate) fn add_subtask(&mut self, index: usize, parent: Option<usize>) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            match parent {
+                Some(parent) => {
+                    if let Some(node) = todo.subtasks.get_mut(parent) {
+                        node.children.push(SubtaskNode::default());
+                        node.collapsed = false;
+                    }
+                }
+                None => todo.subtasks.push(SubtaskNode::default()),
+            }
+        }
+    }
+
+    pub(crate) fn save_subtask(&mut self, index: usize, path: SubtaskPath, label: String) {
+        if let Some(node) = self.todos.get_mut(index).and_then(|t| t.subtask_mut(path)) {
+            node.label = label;
+        }
+    }
+
+    /// Checks every subtask and child subtask of the todo.
+    pub(crate) fn check_all_subtasks(&mut self, index: usize) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            for node in &mut todo.subtasks {
+                node.checked = true;
+                for child in &mut node.children {
+                    child.checked = true;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn check_subtask(&mut self, index: usize, path: SubtaskPath) {
+        if let Some(node) = self.todos.get_mut(index).and_then(|t| t.subtask_mut(path)) {
+            node.checked = !node.checked;
+        }
+    }
+
+    pub(crate) fn delete_subtask(&mut self, index: usize, path: SubtaskPath) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.delete_subtask(path);
+        }
+    }
+
+    pub(crate) fn toggle_subtask_collapsed(&mut self, index: usize, node_index: usize) {
+        if let Some(node) = self
+            .todos
+            .get_mut(index)
+            .and_then(|t| t.subtasks.get_mut(node_index))
+        {
+            node.collapsed = !node.collapsed;
+        }
+    }
+
+    /// Moves the todo at `from` to the insertion point `to`, counted before `from` is removed.
+    pub(crate) fn reorder(&mut self, from: usize, to: usize) {
+        let to = to.min(self.todos.len());
+        if from < self.todos.len() && to != from && to != from + 1 {
+            let todo = self.todos.remove(from);
+            let to = if to > from { to - 1 } else { to };
+            self.todos.insert(to, todo);
+        }
+    }
+
+    pub(crate) fn move_up(&mut self, index: usize) {
+        if index > 0 && index < self.todos.len() {
+            self.todos.swap(index - 1, index);
+        }
+    }
+
+    pub(crate) fn move_down(&mut self, index: usize) {
+        if index + 1 < self.todos.len() {
+            self.todos.swap(index, index + 1);
+        }
+    }
+
+    /// Copies every todo on board `from` onto board `to`. `place` gives each copy a fresh id and,
+    /// if known, the position of the original's window.
+    pub(crate) fn duplicate_board(
+        &mut self,
+        from: usize,
+        to: usize,
+        mut place: impl FnMut(&Todo) -> (egui::Id, Option<egui::Pos2>),
+    ) {
+        let copies: Vec<Todo> = self
+            .todos
+            .iter()
+            .filter(|todo| todo.board == from)
+            .map(|todo| {
+                let (id, position) = place(todo);
+                Todo {
+                    id,
+                    board: to,
+                    initial_position: position.or(todo.initial_position),
+                    ..todo.clone()
+                }
+            })
+            .collect();
+        self.todos.extend(copies);
+    }
+
+    /// Adds a todo to `board` for each of the todo's top-level subtasks, each with a fresh id
+    /// from `next_id`. A subtask's children become its todo's subtasks.
+    pub(crate) fn promote_subtasks(
+        &mut self,
+        index: usize,
+        board: usize,
+        mut next_id: impl FnMut() -> egui::Id,
+    ) {
+        let Some(todo) = self.todos.get(index) else {
+            return;
+        };
+        for node in todo.subtasks.clone() {
+            let todo = Todo {
+                z: self.next_z(),
+                checked: node.checked,
+                subtasks: node.children,
+                ..Todo::new(next_id(), board, cap_label(node.label))
+            };
+            self.todos.push(todo);
+        }
+    }
+
+    /// Adds todos read from another workspace to `board`, each with a fresh id from `next_id` so
+    /// none collides with a todo already here. Links between them follow the new ids; links to
+    /// todos that weren't brought along are dropped. They stack above every todo, in the order
+    /// they were stacked in.
+    pub(crate) fn import_board(
+        &mut self,
+        board: usize,
+        mut todos: Vec<Todo>,
+        mut next_id: impl FnMut() -> egui::Id,
+    ) {
+        let ids: HashMap<egui::Id, egui::Id> =
+            todos.iter().map(|todo| (todo.id, next_id())).collect();
+        // The file's stacking can't be trusted to fit above ours, so only its order is kept
+        todos.sort_by_key(|todo| todo.z);
+        let z = self.next_z();
+        for (rank, todo) in (0..).zip(todos) {
+            let links = todo
+                .links
+                .iter()
+                .filter_map(|link| ids.get(link).copied())
+                .collect();
+            self.todos.push(Todo {
+                id: ids[&todo.id],
+                board,
+                links,
+                z: z.saturating_add(rank),
+                ..todo
+            });
+        }
+    }
+
+    /// Stores `rule` as written, even if it doesn't parse yet, so it can be fixed up later.
+    pub(crate) fn set_recurrence(&mut self, index: usize, rule: Option<String>) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.recurrence = rule;
+            todo.updated_at = Utc::now();
+        }
+    }
+
+    pub(crate) fn set_code_lang(&mut self, index: usize, code_lang: Option<String>) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.code_lang = code_lang;
+        }
+    }
+
+    pub(crate) fn set_effort(&mut self, index: usize, effort: Option<u8>) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.effort = effort;
+            todo.updated_at = Utc::now();
+        }
+    }
+
+    pub(crate) fn set_alarm(&mut self, index: usize, alarm: Option<DateTime<Utc>>) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.alarm = alarm;
+        }
+    }
+
+    /// Repeats the todo's alarm `count` more times, `interval` apart, after it goes off.
+    pub(crate) fn set_reminders(&mut self, index: usize, count: u32, interval: Duration) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.remaining_reminders = count;
+            todo.reminder_interval = (count > 0).then_some(interval);
+        }
+    }
+
+    /// Stops the todo's alarm, along with any repeats still to come.
+    pub(crate) fn dismiss_reminders(&mut self, index: usize) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.alarm = None;
+            todo.remaining_reminders = 0;
+            todo.reminder_interval = None;
+        }
+    }
+
+    /// Clears the alarms that have gone off by `now`, or sets them again for their next repeat,
+    /// returning the ids of their todos.
+    pub(crate) fn take_due_alarms(&mut self, now: DateTime<Utc>) -> Vec<egui::Id> {
+        self.todos
+            .iter_mut()
+            .filter(|todo| todo.alarm.is_some_and(|alarm| alarm <= now))
+            .map(|todo| {
+                let repeat = todo
+                    .reminder_interval
+                    .and_then(|interval| chrono::Duration::from_std(interval).ok())
+                    .filter(|_| todo.remaining_reminders > 0);
+                todo.alarm = repeat.map(|interval| now + interval);
+                todo.remaining_reminders = todo.remaining_reminders.saturating_sub(1);
+                if todo.remaining_reminders == 0 {
+                    todo.reminder_interval = None;
+                }
+                todo.id
+            })
+            .collect()
+    }
+
+    /// Unchecks repeating todos whose next occurrence has come.
+    pub(crate) fn reopen_due(&mut self, now: DateTime<Utc>) {
+        for todo in &mut self.todos {
+            if todo.checked && todo.reopens_at.is_some_and(|reopens_at| reopens_at <= now) {
+                todo.checked = false;
+                todo.reopens_at = None;
+                todo.updated_at = now;
+            }
+        }
+    }
+
+    pub(crate) fn snooze(&mut self, index: usize, until: Option<DateTime<Utc>>) {
+        if let Some(todo) = self.todos.get_mut(index) {
+            todo.hidden_until = until;
+        }
+    }
+
+    /// Puts snoozed todos back on the board once their snooze is over at `now`.
+    pub(crate) fn wake_snoozed(&mut self, now: DateTime<Utc>) {
+        for todo in &mut self.todos {
+            if todo.hidden_until.is_some_and(|until| until <= now) {
+                todo.hidden_until = None;
+            }
+        }
+    }
+
+    /// Archives checked todos that have gone unchanged for `delay`.
+    pub(crate) fn archive_expired(&mut self, delay: Duration, now: DateTime<Utc>) {
+        for todo in &mut self.todos {
+            let remaining = todo.archives_in(delay, now);
+            if remaining.is_some_and(|remaining| remaining <= chrono::Duration::zero()) {
+                todo.archived = true;
+            }
+        }
+    }
+}
+
+/// `label` cut to `MAX_LABEL_CHARS` characters, ending in an ellipsis if anything was cut.
+fn cap_label(label: String) -> String {
+    if label.chars().count() <= MAX_LABEL_CHARS {
+        return label;
+    }
+    let mut capped: String = label.chars().take(MAX_LABEL_CHARS - 1).collect();
+    capped.push('…');
+    capped
+}
+
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub(crate) struct Todo {
+    pub(crate) id: egui::Id,
+    /// Index into `AppState::boards`. Todos from before boards existed land on the first one.
+    #[serde(default)]
+    pub(crate) board: usize,
+    pub(crate) label: String,
+    /// Extended notes on the back of the sticky, keeping the front terse.
+    #[serde(default)]
+    pub(crate) back: String,
+    #[serde(default)]
+    pub(crate) showing_back: bool,
+    pub(crate) checked: bool,
+    // Todos persisted before this field existed are stamped with their load time
+    #[serde(default = "Utc::now")]
+    pub(crate) created_at: DateTime<Utc>,
+    /// When the todo was last checked, edited, or recolored.
+    #[serde(default = "Utc::now")]
+    pub(crate) updated_at: DateTime<Utc>,
+    /// Archived todos only show under the Archived filter.
+    #[serde(default)]
+    pub(crate) archived: bool,
+    #[serde(default)]
+    pub(crate) attachment: Option<PathBuf>,
+    #[serde(default = "default_sticky_size")]
+    pub(crate) size: egui::Vec2,
+    /// Fill color of the sticky's window; `None` follows the theme.
+    #[serde(default)]
+    pub(crate) color: Option<egui::Color32>,
+    #[serde(default)]
+    pub(crate) subtasks: Vec<SubtaskNode>,
+    /// A repeat rule in RRULE syntax, kept as the user wrote it. See `recurrence`.
+    #[serde(default)]
+    pub(crate) recurrence: Option<String>,
+    /// When a checked repeating todo is unchecked again.
+    #[serde(default)]
+    pub(crate) reopens_at: Option<DateTime<Utc>>,
+    /// Obscures the label on screen for privacy on a shared screen. This is not encryption:
+    /// the label is still stored in plaintext.
+    #[serde(default)]
+    pub(crate) hidden: bool,
+    /// Pinned stickies stay where they are, and can't be dragged around the canvas.
+    #[serde(default)]
+    pub(crate) pinned: bool,
+    /// When the sticky next jumps to the front and flashes, to nag about it.
+    #[serde(default)]
+    pub(crate) alarm: Option<DateTime<Utc>>,
+    /// How many more times the alarm goes off again after it next does, each
+    /// `reminder_interval` later, until it's dismissed.
+    #[serde(default)]
+    pub(crate) remaining_reminders: u32,
+    #[serde(default)]
+    pub(crate) reminder_interval: Option<Duration>,
+    /// Snoozed todos are left off the board until this passes.
+    #[serde(default)]
+    pub(crate) hidden_until: Option<DateTime<Utc>>,
+    /// An estimate of the work left, in points, for planning with the board.
+    #[serde(default)]
+    pub(crate) effort: Option<u8>,
+    /// Other todos this one refers to, like ones it's blocked by, in the order they were linked.
+    #[serde(default)]
+    pub(crate) links: Vec<egui::Id>,
+    /// The language the label is highlighted as, shown as code. `None` shows plain text.
+    #[serde(default)]
+    pub(crate) code_lang: Option<String>,
+    /// Where the sticky sits in the stack of overlapping windows; higher is nearer the front.
+    #[serde(default)]
+    pub(crate) z: u32,
+    /// Stickies start each session read-only, even if saved while being edited.
+    #[serde(skip)]
+    pub(crate) edit_mode: bool,
+    /// Whether a hidden label is temporarily shown. Hidden stickies start concealed each session.
+    #[serde(skip)]
+    pub(crate) revealed: bool,
+    /// Where a new sticky's window first opens; afterwards egui remembers its position.
+    #[serde(skip)]
+    pub(crate) initial_position: Option<egui::Pos2>,
+    /// A position to move the window to on the next frame, after it's dropped near alignment.
+    #[serde(skip)]
+    pub(crate) snap_position: Option<egui::Pos2>,
+}
+
+impl Todo {
+    pub(crate) fn new(id: egui::Id, board: usize, label: String) -> Self {
+        Self {
+            id,
+            board,
+            label,
+            back: String::new(),
+            showing_back: false,
+            checked: false,
+            edit_mode: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            archived: false,
+            attachment: None,
+            size: DEFAULT_STICKY_SIZE,
+            color: None,
+            subtasks: vec![],
+            recurrence: None,
+            reopens_at: None,
+            hidden: false,
+            pinned: false,
+            alarm: None,
+            remaining_reminders: 0,
+            reminder_interval: None,
+            hidden_until: None,
+            effort: None,
+            links: vec![],
+            code_lang: None,
+            z: 0,
+            revealed: false,
+            initial_position: None,
+            snap_position: None,
+        }
+    }
+
+    pub(crate) fn is_concealed(&self) -> bool {
+        self.hidden && !self.revealed
+    }
+
+    /// How long until a checked todo is auto-archived after `delay`, if it's waiting to be.
+    pub(crate) fn archives_in(
+        &self,
+        delay: Duration,
+        now: DateTime<Utc>,
+    ) -> Option<chrono::Duration> {
+        // Repeating todos come back rather than being put away
+        if !self.checked || self.archived || self.reopens_at.is_some() {
+            return None;
+        }
+        let delay = chrono::Duration::from_std(delay).ok()?;
+        Some(delay - (now - self.updated_at))
+    }
+
+    /// How many subtasks and child subtasks are still unchecked.
+    pub(crate) fn open_subtasks(&self) -> usize {
+        self.subtasks
+            .iter()
+            .flat_map(|node| std::iter::once(node).chain(&node.children))
+            .filter(|node| !node.checked)
+            .count()
+    }
+
+    /// The `#tags` written in the label, lowercased and without the `#`.
+    pub(crate) fn tags(&self) -> impl Iterator<Item = String> + '_ {
+        self.label.split_whitespace().filter_map(|word| {
+            let tag = word
+                .strip_prefix('#')?
+                .trim_end_matches(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'));
+            (!tag.is_empty()).then(|| tag.to_lowercase())
+        })
+    }
+
+    pub(crate) fn recurrence(&self) -> Option<Recurrence> {
+        self.recurrence.as_ref()?.parse().ok()
+    }
+
+    fn subtask_mut(&mut self, path: SubtaskPath) -> Option<&mut SubtaskNode> {
+        let node = self.subtasks.get_mut(path.node)?;
+        match path.child {
+            Some(child) => node.children.get_mut(child),
+            None => Some(node),
+        }
+    }
+
+    fn delete_subtask(&mut self, path: SubtaskPath) {
+        match path.child {
+            Some(child) => {
+                if let Some(node) = self.subtasks.get_mut(path.node) {
+                    if child < node.children.len() {
+                        node.children.remove(child);
+                    }
+                }
+            }
+            None => {
+                if path.node < self.subtasks.len() {
+                    self.subtasks.remove(path.node);
+                }
+            }
+        }
+    }
+}
+
+fn default_sticky_size() -> egui::Vec2 {
+    DEFAULT_STICKY_SIZE
+}
+
+/// A checklist item within a sticky. Top-level subtasks can hold one level of children.
+#[derive(Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct SubtaskNode {
+    pub(crate) label: String,
+    pub(crate) checked: bool,
+    /// Hides the children; only meaningful for top-level subtasks.
+    pub(crate) collapsed: bool,
+    pub(crate) children: Vec<SubtaskNode>,
+}
+
+/// Locates a subtask within a sticky: a top-level node, or one of its children.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) struct SubtaskPath {
+    pub(crate) node: usize,
+    pub(crate) child: Option<usize>,
+}
+
+impl SubtaskPath {
+    pub(crate) fn node(node: usize) -> Self {
+        Self { node, child: None }
+    }
+
+    pub(crate) fn child(node: usize, child: usize) -> Self {
+        Self {
+            node,
+            child: Some(child),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn core_with(labels: &[&str]) -> Core {
+        let mut core = Core::default();
+        for (i, label) in labels.iter().enumerate() {
+            core.add_todo(egui::Id::new(i), 0, label.to_string());
+        }
+        core
+    }
+
+    fn labels(core: &Core) -> Vec<&str> {
+        core.todos.iter().map(|todo| todo.label.as_str()).collect()
+    }
+
+    #[test]
+    fn add_todo_clears_draft() {
+        let mut core = Core::default();
+        core.add_todo(egui::Id::new(0), 0, "Feed doge".to_owned());
+
+        assert_eq!(labels(&core), ["Feed doge"]);
+        assert!(core.draft.is_empty());
+    }
+
+    #[test]
+    fn long_labels_are_capped_by_chars() {
+        let mut core = core_with(&[]);
+        core.add_todo(egui::Id::new(0), 0, "ü".repeat(MAX_LABEL_CHARS + 10));
+        let label = &core.todos[0].label;
+        assert_eq!(label.chars().count(), MAX_LABEL_CHARS);
+        assert!(label.ends_with('…'));
+
+        core.save_label(0, "ü".repeat(MAX_LABEL_CHARS));
+        assert_eq!(core.todos[0].label, "ü".repeat(MAX_LABEL_CHARS));
+    }
+
+    #[test]
+    fn deleting_prunes_links_to_the_todo() {
+        let mut core = core_with(&["Feed doge", "Buy kibble", "Walk doge"]);
+        let kibble = core.todos[1].id;
+        core.link_todos(0, kibble);
+        core.link_todos(0, kibble);
+        core.link_todos(0, core.todos[0].id);
+        assert_eq!(core.todos[0].links, [kibble]);
+
+        core.delete_todo(1);
+        assert!(core.todos[0].links.is_empty());
+    }
+
+    #[test]
+    fn raise_stacks_above_others_once() {
+        let mut core = core_with(&["Feed doge", "Walk doge", "Pet doge"]);
+        core.raise(0);
+        assert_eq!(core.todos[0].z, 3);
+
+        core.raise(0);
+        assert_eq!(core.todos[0].z, 3);
+        assert_eq!(core.next_z(), 4);
+    }
+
+    #[test]
+    fn unchecking_unarchives() {
+        let mut core = core_with(&["Feed doge"]);
+        core.check_todo(0, Utc::now());
+        assert!(core.todos[0].checked);
+
+        core.todos[0].archived = true;
+        core.check_todo(0, Utc::now());
+        assert!(!core.todos[0].checked);
+        assert!(!core.todos[0].archived);
+    }
+
+    #[test]
+    fn out_of_range_indices_are_ignored() {
+        let mut core = core_with(&["Feed doge"]);
+        core.check_todo(1, Utc::now());
+        core.delete_todo(1);
+        core.move_down(0);
+
+        assert_eq!(labels(&core), ["Feed doge"]);
+        assert!(!core.todos[0].checked);
+    }
+
+    #[test]
+    fn reorder_counts_insertion_point_before_removal() {
+        let mut core = core_with(&["a", "b", "c"]);
+        core.reorder(0, 2);
+        assert_eq!(labels(&core), ["b", "a", "c"]);
+
+        core.reorder(2, 0);
+        assert_eq!(labels(&core), ["c", "b", "a"]);
+    }
+
+    #[test]
+    fn check_all_subtasks_closes_children_too() {
+        let mut core = core_with(&["Feed doge"]);
+        core.add_subtask(0, None);
+        core.add_subtask(0, Some(0));
+        core.add_subtask(0, Some(0));
+        core.check_subtask(0, SubtaskPath::child(0, 0));
+        assert_eq!(core.todos[0].open_subtasks(), 2);
+
+        core.check_all_subtasks(0);
+        assert_eq!(core.todos[0].open_subtasks(), 0);
+    }
+
+    #[test]
+    fn delete_subtask_removes_only_the_child() {
+        let mut core = core_with(&["Feed doge"]);
+        core.add_subtask(0, None);
+        core.add_subtask(0, Some(0));
+        core.add_subtask(0, Some(0));
+        core.save_subtask(0, SubtaskPath::child(0, 1), "kibble".to_owned());
+
+        core.delete_subtask(0, SubtaskPath::child(0, 0));
+
+        let children = &core.todos[0].subtasks[0].children;
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].label, "kibble");
+    }
+
+    #[test]
+    fn archive_expired_waits_for_the_delay() {
+        let mut core = core_with(&["done", "not done"]);
+        core.check_todo(0, Utc::now());
+        let delay = Duration::from_secs(60);
+        let checked_at = core.todos[0].updated_at;
+
+        core.archive_expired(delay, checked_at + chrono::Duration::seconds(30));
+        assert!(!core.todos[0].archived);
+
+        core.archive_expired(delay, checked_at + chrono::Duration::seconds(60));
+        assert!(core.todos[0].archived);
+        assert!(!core.todos[1].archived);
+    }
+
+    #[test]
+    fn duplicate_todo_inserts_copy_after_original() {
+        let mut core = core_with(&["a", "b"]);
+        core.todos[0].checked = true;
+
+        core.duplicate_todo(0, egui::Id::new("copy"), None);
+
+        let labels: Vec<&str> = core.todos.iter().map(|t| t.label.as_str()).collect();
+        assert_eq!(labels, ["a", "a", "b"]);
+        assert_eq!(core.todos[1].id, egui::Id::new("copy"));
+        assert!(core.todos[1].checked);
+    }
+
+    #[test]
+    fn duplicate_board_copies_with_fresh_ids() {
+        let mut core = core_with(&["a", "b"]);
+        core.add_todo(egui::Id::new("other"), 1, "elsewhere".to_owned());
+
+        let mut next = 100;
+        core.duplicate_board(0, 2, |_| {
+            next += 1;
+            (egui::Id::new(next), None)
+        });
+
+        let copies: Vec<&Todo> = core.todos.iter().filter(|t| t.board == 2).collect();
+        assert_eq!(copies.len(), 2);
+        assert_eq!(copies[0].label, "a");
+        assert!(copies
+            .iter()
+            .all(|copy| core.todos.iter().filter(|t| t.id == copy.id).count() == 1));
+    }
+
+    #[test]
+    fn import_board_renumbers_ids_and_links() {
+        let mut core = core_with(&["here"]);
+        let mut imported = core_with(&["a", "b"]).todos;
+        imported[0].links = vec![imported[1].id, egui::Id::new("elsewhere")];
+
+        let mut next = 100;
+        core.import_board(1, imported, || {
+            next += 1;
+            egui::Id::new(next)
+        });
+
+        let (a, b) = (&core.todos[1], &core.todos[2]);
+        assert_eq!((a.board, b.board), (1, 1));
+        assert_eq!(a.id, egui::Id::new(101));
+        assert_eq!(a.links, [b.id]);
+        assert!(a.z > core.todos[0].z && b.z > a.z);
+
+        let mut imported = core_with(&["top", "bottom"]).todos;
+        imported[0].z = u32::MAX;
+        core.import_board(2, imported, || {
+            next += 1;
+            egui::Id::new(next)
+        });
+        let (bottom, top) = (&core.todos[3], &core.todos[4]);
+        assert_eq!(
+            (bottom.label.as_str(), top.label.as_str()),
+            ("bottom", "top")
+        );
+        assert!(bottom.z > core.todos[2].z && top.z == bottom.z + 1);
+    }
+
+    #[test]
+    fn promote_subtasks_makes_each_a_todo() {
+        let mut core = core_with(&["Move house"]);
+        core.todos[0].subtasks = vec![
+            SubtaskNode {
+                label: "Pack".to_owned(),
+                children: vec![SubtaskNode {
+                    label: "Books".to_owned(),
+                    ..SubtaskNode::default()
+                }],
+                ..SubtaskNode::default()
+            },
+            SubtaskNode {
+                label: "Book van".to_owned(),
+                checked: true,
+                ..SubtaskNode::default()
+            },
+        ];
+
+        let mut next = 100;
+        core.promote_subtasks(0, 1, || {
+            next += 1;
+            egui::Id::new(next)
+        });
+
+        assert_eq!(labels(&core), ["Move house", "Pack", "Book van"]);
+        let (pack, van) = (&core.todos[1], &core.todos[2]);
+        assert_eq!((pack.board, van.board), (1, 1));
+        assert_eq!((pack.checked, van.checked), (false, true));
+        assert_eq!(pack.subtasks[0].label, "Books");
+        assert_eq!(core.todos[0].subtasks.len(), 2);
+    }
+
+    #[test]
+    fn tags_are_hashed_words() {
+        let core = core_with(&["Call #Mom, re #home-stuff # #"]);
+
+        let tags: Vec<String> = core.todos[0].tags().collect();
+        assert_eq!(tags, ["mom", "home-stuff"]);
+    }
+
+    #[test]
+    fn wake_snoozed_only_wakes_expired() {
+        let mut core = core_with(&["a", "b"]);
+        let now = Utc::now();
+        core.snooze(0, Some(now - chrono::Duration::minutes(1)));
+        core.snooze(1, Some(now + chrono::Duration::minutes(1)));
+
+        core.wake_snoozed(now);
+        assert_eq!(core.todos[0].hidden_until, None);
+        assert!(core.todos[1].hidden_until.is_some());
+    }
+
+    #[test]
+    fn reminders_repeat_until_used_up_or_dismissed() {
+        let mut core = core_with(&["a", "b"]);
+        let now = Utc::now();
+        for index in 0..2 {
+            core.set_alarm(index, Some(now));
+            core.set_reminders(index, 2, Duration::from_secs(60));
+        }
+
+        assert_eq!(core.take_due_alarms(now).len(), 2);
+        assert_eq!(
+            core.todos[0].alarm,
+            Some(now + chrono::Duration::minutes(1))
+        );
+        assert_eq!(core.todos[0].remaining_reminders, 1);
+
+        core.dismiss_reminders(1);
+        let later = now + chrono::Duration::minutes(1);
+        assert_eq!(core.take_due_alarms(later), [egui::Id::new(0)]);
+        // The last repeat goes off without setting another
+        assert_eq!(
+            core.todos[0].alarm,
+            Some(later + chrono::Duration::minutes(1))
+        );
+        assert_eq!(
+            core.take_due_alarms(later + chrono::Duration::minutes(1))
+                .len(),
+            1
+        );
+        assert_eq!(core.todos[0].alarm, None);
+    }
+}
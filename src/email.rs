@@ -0,0 +1,79 @@
+//! Emailing stickies as their alarms go off, for users who'd rather be reminded in their inbox.
+
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, Message, SmtpTransport,
+    Transport,
+};
+
+/// The account reminders are sent through, over STARTTLS on the submission port, 587.
+#[derive(Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct EmailSettings {
+    /// The SMTP server's host name, like `smtp.example.com`.
+    pub server: String,
+    pub username: String,
+    /// Saved with the rest of the settings, so it's only kept from prying eyes while saved
+    /// data is encrypted.
+    pub password: String,
+    /// Where reminders are sent, and who they're from.
+    pub address: String,
+}
+
+impl EmailSettings {
+    /// Emails `label` as a reminder, waiting on the server. Fails with why it wasn't sent.
+    pub fn send(&self, label: &str) -> Result<(), String> {
+        let address: Mailbox = self
+            .address
+            .parse()
+            .map_err(|err| format!("\"{}\" isn't an email address: {err}", self.address))?;
+        let message = Message::builder()
+            .from(address.clone())
+            .to(address)
+            .subject(format!("Reminder: {}", subject_line(label)))
+            .body(label.to_owned())
+            .map_err(|err| err.to_string())?;
+        let transport = SmtpTransport::starttls_relay(&self.server)
+            .map_err(|err| err.to_string())?
+            .credentials(Credentials::new(
+                self.username.clone(),
+                self.password.clone(),
+            ))
+            .build();
+        transport.send(&message).map_err(|err| err.to_string())?;
+        Ok(())
+    }
+}
+
+/// The label's first line, cut short to keep subjects readable in an inbox.
+fn subject_line(label: &str) -> String {
+    const MAX_CHARS: usize = 60;
+    let line = label.lines().next().unwrap_or_default().trim();
+    if line.chars().count() <= MAX_CHARS {
+        return line.to_owned();
+    }
+    let cut: String = line.chars().take(MAX_CHARS - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subjects_take_the_first_line_cut_short() {
+        assert_eq!(subject_line("Feed doge\nTwo scoops"), "Feed doge");
+        let long = format!("{} doge", "very ".repeat(20));
+        let subject = subject_line(&long);
+        assert_eq!(subject.chars().count(), 60);
+        assert!(subject.ends_with("very…"));
+    }
+
+    #[test]
+    fn sending_to_a_bad_address_fails_without_connecting() {
+        let settings = EmailSettings {
+            address: "doge".to_owned(),
+            ..EmailSettings::default()
+        };
+        assert!(settings.send("Feed doge").is_err());
+    }
+}
@@ -0,0 +1,107 @@
+//! Optional passphrase-based encryption of the persisted app state.
+//!
+//! Only the app's own state is covered; egui's memory (window positions and sizes) is persisted
+//! by eframe separately and stays in plain text.
+
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+
+/// Storage key for the encrypted state. It's kept apart from `eframe::APP_KEY` so that startup
+/// can tell whether to ask for a passphrase before loading anything.
+pub const ENCRYPTED_KEY: &str = "encrypted_app";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// The persisted form of the encrypted app state.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct EncryptedState {
+    salt: Vec<u8>,
+    nonce: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+impl EncryptedState {
+    /// Decrypts with `passphrase`, also returning the cipher to re-encrypt future saves with.
+    pub fn decrypt(&self, passphrase: &str) -> Result<(Vec<u8>, Cipher), Error> {
+        let salt = self
+            .salt
+            .as_slice()
+            .try_into()
+            .map_err(|_| Error::Corrupt)?;
+        if self.nonce.len() != NONCE_LEN {
+            return Err(Error::Corrupt);
+        }
+
+        let cipher = Cipher::with_salt(passphrase, salt)?;
+        let plaintext = cipher
+            .aes
+            .decrypt(Nonce::from_slice(&self.nonce), self.ciphertext.as_slice())
+            .map_err(|_| Error::WrongPassphrase)?;
+        Ok((plaintext, cipher))
+    }
+}
+
+/// A key derived from the user's passphrase, held in memory while the app is unlocked.
+pub struct Cipher {
+    salt: [u8; SALT_LEN],
+    aes: Aes256Gcm,
+}
+
+impl Cipher {
+    /// Derives a key from `passphrase` with a fresh random salt.
+    pub fn new(passphrase: &str) -> Result<Self, Error> {
+        let mut salt = [0; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self::with_salt(passphrase, salt)
+    }
+
+    fn with_salt(passphrase: &str, salt: [u8; SALT_LEN]) -> Result<Self, Error> {
+        let mut key = [0; 32];
+        argon2::Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|_| Error::KeyDerivation)?;
+
+        Ok(Self {
+            salt,
+            aes: Aes256Gcm::new(&key.into()),
+        })
+    }
+
+    /// Encrypts `plaintext` under a fresh nonce.
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<EncryptedState, Error> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .aes
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| Error::Encryption)?;
+
+        Ok(EncryptedState {
+            salt: self.salt.to_vec(),
+            nonce: nonce.to_vec(),
+            ciphertext,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    KeyDerivation,
+    Encryption,
+    WrongPassphrase,
+    Corrupt,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            Self::KeyDerivation => "could not derive a key from the passphrase",
+            Self::Encryption => "could not encrypt the app state",
+            Self::WrongPassphrase => "wrong passphrase",
+            Self::Corrupt => "the encrypted data is corrupt",
+        };
+        f.write_str(message)
+    }
+}
@@ -0,0 +1,199 @@
+//! Persistence to a file of the user's choosing, e.g. in a synced Dropbox or iCloud folder,
+//! instead of eframe's platform-specific data directory.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::SystemTime,
+};
+
+use notify::{RecursiveMode, Watcher};
+
+/// A key-value store saved as RON, in the same shape eframe uses for its own storage file.
+pub struct FileStorage {
+    path: PathBuf,
+    entries: HashMap<String, String>,
+    dirty: bool,
+    /// When the file was last changed as of reading or writing it, to tell when something
+    /// else, like another instance or a sync, changes it.
+    modified: Option<SystemTime>,
+    /// Set by `watcher` when the file may have changed, until taken.
+    touched: Arc<AtomicBool>,
+    watcher: Option<notify::RecommendedWatcher>,
+}
+
+impl FileStorage {
+    /// Opens the storage file at `path`, creating it if there isn't one. Fails if the file
+    /// can't be parsed, or can't be written to, so saves don't silently go nowhere.
+    pub fn open(path: PathBuf) -> io::Result<Self> {
+        let entries = read_entries(&path)?;
+
+        fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&path)?;
+
+        Ok(Self {
+            modified: modified(&path),
+            path,
+            entries,
+            dirty: false,
+            touched: Arc::default(),
+            watcher: None,
+        })
+    }
+
+    /// Watches the file for changes made elsewhere, waking `ctx` when there may be some.
+    pub fn watch(&mut self, ctx: &egui::Context) {
+        let name = self.path.file_name().map(ToOwned::to_owned);
+        let touched = self.touched.clone();
+        let ctx = ctx.clone();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            if event
+                .paths
+                .iter()
+                .any(|path| path.file_name() == name.as_deref())
+            {
+                touched.store(true, Ordering::Relaxed);
+                ctx.request_repaint();
+            }
+        });
+        // The folder's watched rather than the file, since saves replace the file
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let watched = watcher.and_then(|mut watcher| {
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+            Ok(watcher)
+        });
+        match watched {
+            Ok(watcher) => self.watcher = Some(watcher),
+            Err(err) => log::warn!(
+                "Can't watch {} for changes made elsewhere: {err}",
+                self.path.display()
+            ),
+        }
+    }
+
+    /// Whether the watcher has seen the file change since this was last called. That includes
+    /// changes made here, which `changed_on_disk` tells apart.
+    pub fn take_touched(&self) -> bool {
+        self.touched.swap(false, Ordering::Relaxed)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the file has changed since it was last read or written here.
+    pub fn changed_on_disk(&self) -> bool {
+        modified(&self.path) != self.modified
+    }
+
+    /// Reads the file again, dropping anything set here since that wasn't written.
+    pub fn reload(&mut self) -> io::Result<()> {
+        self.entries = read_entries(&self.path)?;
+        self.modified = modified(&self.path);
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Lets the next flush write over the file's changes on disk.
+    pub fn overwrite_changes(&mut self) {
+        self.modified = modified(&self.path);
+        self.dirty = true;
+    }
+
+    fn write(&mut self) -> io::Result<()> {
+        let text = ron::ser::to_string_pretty(&self.entries, Default::default())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        // Write beside the target and rename over it, so a crash mid-save can't truncate it
+        let temp_path = self.path.with_extension("tmp");
+        fs::write(&temp_path, text)?;
+        fs::rename(&temp_path, &self.path)?;
+        self.modified = modified(&self.path);
+        Ok(())
+    }
+}
+
+fn read_entries(path: &Path) -> io::Result<HashMap<String, String>> {
+    match fs::read_to_string(path) {
+        Ok(text) if text.trim().is_empty() => Ok(HashMap::new()),
+        Ok(text) => {
+            ron::from_str(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(err) => Err(err),
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+impl eframe::Storage for FileStorage {
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.entries.get(key).cloned()
+    }
+
+    fn set_string(&mut self, key: &str, value: String) {
+        if self.entries.get(key) != Some(&value) {
+            self.entries.insert(key.to_owned(), value);
+            self.dirty = true;
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.dirty {
+            return;
+        }
+
+        match self.write() {
+            Ok(()) => self.dirty = false,
+            Err(err) => log::error!("Failed to save to {}: {err}", self.path.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watching_tells_changes_made_elsewhere_from_saves() {
+        let dir = std::env::temp_dir().join("stickies-watch-test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("stickies.ron");
+        fs::remove_file(&path).ok();
+        let mut storage = FileStorage::open(path.clone()).unwrap();
+        storage.watch(&egui::Context::default());
+        let touched = |storage: &FileStorage| {
+            (0..100).any(|_| {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+                storage.take_touched()
+            })
+        };
+
+        eframe::Storage::set_string(&mut storage, "key", "mine".to_owned());
+        eframe::Storage::flush(&mut storage);
+        assert!(touched(&storage));
+        assert!(!storage.changed_on_disk());
+
+        // Written a moment later, so the modification time moves on
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        fs::write(&path, "{\"key\": \"theirs\"}").unwrap();
+        assert!(touched(&storage));
+        assert!(storage.changed_on_disk());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
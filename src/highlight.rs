@@ -0,0 +1,55 @@
+//! Syntax highlighting for stickies holding code, with syntect's grammars by way of egui_extras.
+
+use egui_extras::syntax_highlighting::{self, CodeTheme};
+
+/// The languages a sticky can be highlighted as, by the name stored in `Todo::code_lang`.
+pub const LANGUAGES: [&str; 4] = ["Rust", "Python", "JavaScript", "Shell"];
+
+/// Lays out `code` in the monospace font, colored for the language named `lang` in the theme
+/// matching the light or dark mode. Unknown languages are laid out uncolored.
+pub fn highlight(ctx: &egui::Context, code: &str, lang: &str) -> egui::text::LayoutJob {
+    let style = ctx.style();
+    let theme = CodeTheme::from_style(&style);
+    let mut job = syntax_highlighting::highlight(ctx, &theme, code, extension(lang));
+    // egui_extras lays out at a fixed size, which wouldn't follow the sticky's text size
+    let font = egui::TextStyle::Monospace.resolve(&style);
+    for section in &mut job.sections {
+        section.format.font_id = font.clone();
+    }
+    job
+}
+
+/// The file extension syntect finds `lang`'s grammar by, since its names for some aren't ours,
+/// like "Bourne Again Shell (bash)" for the shell.
+fn extension(lang: &str) -> &str {
+    match lang {
+        "Rust" => "rs",
+        "Python" => "py",
+        "JavaScript" => "js",
+        "Shell" => "sh",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_each_language() {
+        let ctx = egui::Context::default();
+        for lang in LANGUAGES {
+            let job = highlight(&ctx, "if x == 1 {\n  print(\"doge\") # 2\n}", lang);
+            let colors: Vec<egui::Color32> = job
+                .sections
+                .iter()
+                .map(|section| section.format.color)
+                .collect();
+            assert!(colors.windows(2).any(|pair| pair[0] != pair[1]), "{lang}");
+        }
+
+        let plain = highlight(&ctx, "print(\"doge\")", "Doge");
+        assert_eq!(plain.sections.len(), 1);
+        assert_eq!(plain.text, "print(\"doge\")");
+    }
+}
@@ -0,0 +1,109 @@
+//! Translations of the UI's strings, picked from the Settings menu.
+//!
+//! Only the strings seen most are translated so far; the rest stay in English whatever the
+//! language.
+
+/// A language the UI can be shown in.
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum Lang {
+    #[default]
+    English,
+    Spanish,
+    German,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 3] = [Lang::English, Lang::Spanish, Lang::German];
+
+    /// The language's name in itself, so it can be found without reading the current one.
+    pub fn name(self) -> &'static str {
+        match self {
+            Lang::English => "English",
+            Lang::Spanish => "Español",
+            Lang::German => "Deutsch",
+        }
+    }
+}
+
+/// A translated UI string.
+#[derive(Clone, Copy)]
+pub enum Text {
+    Heading,
+    AddSticky,
+    Save,
+    Edit,
+    Delete,
+    Hide,
+    Unhide,
+    Flip,
+    Search,
+    SearchHint,
+    NoMatches,
+    Canvas,
+    List,
+    Detail,
+    Review,
+    Agenda,
+    All,
+    Active,
+    Completed,
+    Archived,
+    ClearCompleted,
+    Undo,
+    Redo,
+    AddZone,
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))] // no file system access on web
+    Import,
+    Export,
+    Settings,
+    Language,
+}
+
+impl Text {
+    pub fn translate(self, lang: Lang) -> &'static str {
+        let [english, spanish, german] = self.translations();
+        match lang {
+            Lang::English => english,
+            Lang::Spanish => spanish,
+            Lang::German => german,
+        }
+    }
+
+    /// The string in each language, in the order of `Lang::ALL`.
+    fn translations(self) -> [&'static str; 3] {
+        match self {
+            Text::Heading => ["Stickies", "Notas adhesivas", "Haftnotizen"],
+            Text::AddSticky => ["Add a sticky: ", "Añadir una nota: ", "Notiz hinzufügen: "],
+            Text::Save => ["Save", "Guardar", "Speichern"],
+            Text::Edit => ["Edit", "Editar", "Bearbeiten"],
+            Text::Delete => ["Delete", "Eliminar", "Löschen"],
+            Text::Hide => ["Hide", "Ocultar", "Verbergen"],
+            Text::Unhide => ["Unhide", "Mostrar", "Anzeigen"],
+            Text::Flip => ["Flip", "Voltear", "Umdrehen"],
+            Text::Search => ["Search", "Buscar", "Suchen"],
+            Text::SearchHint => ["Search stickies", "Buscar notas", "Notizen suchen"],
+            Text::NoMatches => [
+                "No matching stickies",
+                "Ninguna nota coincide",
+                "Keine passenden Notizen",
+            ],
+            Text::Canvas => ["Canvas", "Lienzo", "Leinwand"],
+            Text::List => ["List", "Lista", "Liste"],
+            Text::Detail => ["Detail", "Detalle", "Detail"],
+            Text::Review => ["Review", "Resumen", "Rückblick"],
+            Text::Agenda => ["Agenda", "Agenda", "Termine"],
+            Text::All => ["All", "Todas", "Alle"],
+            Text::Active => ["Active", "Activas", "Offen"],
+            Text::Completed => ["Completed", "Completadas", "Erledigt"],
+            Text::Archived => ["Archived", "Archivadas", "Archiviert"],
+            Text::ClearCompleted => ["Clear completed", "Borrar completadas", "Erledigte löschen"],
+            Text::Undo => ["Undo", "Deshacer", "Rückgängig"],
+            Text::Redo => ["Redo", "Rehacer", "Wiederholen"],
+            Text::AddZone => ["Add zone", "Añadir zona", "Bereich hinzufügen"],
+            Text::Import => ["Import", "Importar", "Importieren"],
+            Text::Export => ["Export", "Exportar", "Exportieren"],
+            Text::Settings => ["Settings", "Ajustes", "Einstellungen"],
+            Text::Language => ["Language: ", "Idioma: ", "Sprache: "],
+        }
+    }
+}
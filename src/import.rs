@@ -0,0 +1,81 @@
+//! Reading notes exported from other sticky note apps, like macOS Stickies' "Export Text…".
+//!
+//! Notes are plain text, separated by a line of three or more dashes or by a form feed. A note
+//! can start with a `Color: <name>` line, since plain text exports otherwise lose the color.
+
+/// One note read from an export, before it becomes a todo.
+#[derive(Debug, PartialEq)]
+pub struct ImportedNote {
+    /// The note's first line.
+    pub label: String,
+    /// Any lines after the first, for the back of the sticky.
+    pub back: String,
+    /// The color named on a `Color:` line, as written.
+    pub color: Option<String>,
+}
+
+/// Splits `text` into notes, skipping any that are blank.
+pub fn parse_notes(text: &str) -> Vec<ImportedNote> {
+    let mut notes = vec![];
+    let mut lines = vec![];
+    for line in text.lines() {
+        if is_separator(line) {
+            notes.extend(parse_note(&lines));
+            lines.clear();
+        } else {
+            lines.push(line);
+        }
+    }
+    notes.extend(parse_note(&lines));
+    notes
+}
+
+fn is_separator(line: &str) -> bool {
+    let line = line.trim();
+    line == "\u{c}" || (line.len() >= 3 && line.chars().all(|c| c == '-'))
+}
+
+fn parse_note(lines: &[&str]) -> Option<ImportedNote> {
+    let mut lines = lines.iter().map(|line| line.trim_end_matches('\u{c}'));
+    let mut color = None;
+    let label = loop {
+        let line = lines.next()?.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match line.strip_prefix("Color:") {
+            Some(name) if color.is_none() => color = Some(name.trim().to_owned()),
+            _ => break line.to_owned(),
+        }
+    };
+
+    let back = lines.collect::<Vec<_>>().join("\n").trim().to_owned();
+    Some(ImportedNote { label, back, color })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_on_dashes_and_form_feeds() {
+        let text = "Feed doge\nkibble, not cake\n---\n\nColor: Blue\nWalk doge\n\u{c}\n\n-----\n";
+        let notes = parse_notes(text);
+
+        assert_eq!(
+            notes,
+            [
+                ImportedNote {
+                    label: "Feed doge".to_owned(),
+                    back: "kibble, not cake".to_owned(),
+                    color: None,
+                },
+                ImportedNote {
+                    label: "Walk doge".to_owned(),
+                    back: String::new(),
+                    color: Some("Blue".to_owned()),
+                },
+            ]
+        );
+    }
+}
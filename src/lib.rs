@@ -1,4 +1,19 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 mod app;
+mod core;
+#[cfg(not(target_arch = "wasm32"))]
+mod email;
+mod encryption;
+#[cfg(not(target_arch = "wasm32"))]
+mod file_storage;
+mod highlight;
+mod i18n;
+mod import;
+mod natural_date;
+mod recovery;
+mod recurrence;
+mod spellcheck;
+#[cfg(any(windows, target_os = "macos"))]
+mod tray;
 pub use app::AppState;
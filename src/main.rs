@@ -6,10 +6,20 @@
 fn main() -> eframe::Result<()> {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
 
+    if std::env::args()
+        .skip(1)
+        .any(|arg| arg == "--help" || arg == "-h")
+    {
+        print!("{USAGE}");
+        return Ok(());
+    }
+
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([400.0, 300.0])
             .with_min_inner_size([300.0, 220.0])
+            // Lets `Settings::window_opacity` show the desktop through, where it's supported
+            .with_transparent(true)
             .with_icon(
                 // NOTE: Adding an icon is optional
                 eframe::icon_data::from_png_bytes(&include_bytes!("../assets/icon-256.png")[..])
@@ -24,6 +34,20 @@ fn main() -> eframe::Result<()> {
     )
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+const USAGE: &str = "\
+Usage: stickies [OPTIONS] [LABEL]... [-- LABEL...]
+
+Adds a sticky for each LABEL.
+
+Options:
+  --storage <FILE>  Keep stickies in FILE, e.g. in a synced folder. FILE is watched, and
+                    you're asked what to keep when it's changed elsewhere. Stickies kept
+                    in the default location aren't watched.
+  --fresh           Start with an empty board, ignoring saved stickies
+  -h, --help        Print this help
+";
+
 // When compiling to web using trunk:
 #[cfg(target_arch = "wasm32")]
 fn main() {
@@ -0,0 +1,176 @@
+//! Dates written in plain words at the end of a new sticky, like "Pay rent tomorrow" or
+//! "Call Bob Friday at 3pm", so the sticky can set its own alarm.
+//!
+//! Only a day word (today, tonight, tomorrow or a weekday), a time (3pm, 3:30pm or 15:00), or a
+//! day followed by a time are understood. Anything else is left as part of the label.
+
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+
+/// The hour a day without a time lands on.
+const DEFAULT_HOUR: u32 = 9;
+/// The hour "tonight" lands on.
+const TONIGHT_HOUR: u32 = 20;
+
+/// Splits a trailing date phrase off `label`, returning the rest of the label and the next
+/// time after `now` the phrase names. `None` if there's no phrase, nothing but the phrase, or
+/// the phrase names a time that's already gone, like "today 8am" in the afternoon.
+pub fn split_trailing_date(label: &str, now: NaiveDateTime) -> Option<(&str, NaiveDateTime)> {
+    let (mut rest, last) = pop_word(label);
+    let time = parse_time(last);
+    if time.is_some() {
+        let (before, word) = pop_word(rest);
+        rest = if word.eq_ignore_ascii_case("at") {
+            before
+        } else {
+            rest
+        };
+    } else {
+        rest = label.trim_end();
+    }
+
+    let (before, word) = pop_word(rest);
+    let day = parse_day(word);
+    if day.is_some() {
+        let (before_on, on) = pop_word(before);
+        rest = if on.eq_ignore_ascii_case("on") && matches!(day, Some(Day::Weekday(_))) {
+            before_on
+        } else {
+            before
+        };
+    }
+    if rest.is_empty() || (day.is_none() && time.is_none()) {
+        return None;
+    }
+
+    let today = now.date();
+    let when = match day {
+        Some(Day::Today) => today.and_time(time.unwrap_or(at_hour(DEFAULT_HOUR))),
+        Some(Day::Tonight) => today.and_time(time.unwrap_or(at_hour(TONIGHT_HOUR))),
+        Some(Day::Tomorrow) => {
+            (today + Days::new(1)).and_time(time.unwrap_or(at_hour(DEFAULT_HOUR)))
+        }
+        // A weekday means the coming one, never today
+        Some(Day::Weekday(weekday)) => {
+            next_weekday(today, weekday).and_time(time.unwrap_or(at_hour(DEFAULT_HOUR)))
+        }
+        // A time alone means its next occurrence
+        None => {
+            let time = time?;
+            let when = today.and_time(time);
+            if when > now {
+                when
+            } else {
+                (today + Days::new(1)).and_time(time)
+            }
+        }
+    };
+    (when > now).then_some((rest, when))
+}
+
+#[derive(Clone, Copy)]
+enum Day {
+    Today,
+    Tonight,
+    Tomorrow,
+    Weekday(Weekday),
+}
+
+/// The last word of `text`, and what comes before it with trailing whitespace trimmed.
+fn pop_word(text: &str) -> (&str, &str) {
+    let text = text.trim_end();
+    match text.rsplit_once(char::is_whitespace) {
+        Some((before, word)) => (before.trim_end(), word),
+        None => ("", text),
+    }
+}
+
+fn parse_day(word: &str) -> Option<Day> {
+    let word = word.to_lowercase();
+    let day = match word.as_str() {
+        "today" => Day::Today,
+        "tonight" => Day::Tonight,
+        "tomorrow" => Day::Tomorrow,
+        // chrono also takes three-letter names, which would catch words like "sun" and "wed"
+        _ if word.len() > 3 => Day::Weekday(word.parse().ok()?),
+        _ => return None,
+    };
+    Some(day)
+}
+
+/// Reads `3pm`, `3:30pm` or `15:00`.
+fn parse_time(word: &str) -> Option<NaiveTime> {
+    let word = word.to_lowercase();
+    let (clock, meridiem) = if let Some(clock) = word.strip_suffix("am") {
+        (clock, Some(0))
+    } else if let Some(clock) = word.strip_suffix("pm") {
+        (clock, Some(12))
+    } else {
+        (word.as_str(), None)
+    };
+
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) if minute.len() == 2 => (hour.parse().ok()?, minute.parse().ok()?),
+        // A bare number is only a time with am or pm, otherwise "Buy 2" would be 2 o'clock
+        None if meridiem.is_some() => (clock.parse().ok()?, 0),
+        _ => return None,
+    };
+    let hour: u32 = match meridiem {
+        Some(offset) if (1..=12).contains(&hour) => hour % 12 + offset,
+        Some(_) => return None,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn at_hour(hour: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, 0, 0).unwrap_or_default()
+}
+
+fn next_weekday(day: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (7 + weekday.num_days_from_monday() - day.weekday().num_days_from_monday()) % 7;
+    day + Days::new(if ahead == 0 { 7 } else { u64::from(ahead) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_day_and_time_phrases() {
+        // A Wednesday afternoon
+        let now = NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_opt(14, 0, 0)
+            .unwrap();
+        let at = |day, hour, minute| {
+            NaiveDate::from_ymd_opt(2024, 5, day)
+                .unwrap()
+                .and_hms_opt(hour, minute, 0)
+                .unwrap()
+        };
+
+        assert_eq!(
+            split_trailing_date("Pay rent tomorrow", now),
+            Some(("Pay rent", at(2, 9, 0)))
+        );
+        assert_eq!(
+            split_trailing_date("Call Bob on Friday at 3:30pm", now),
+            Some(("Call Bob", at(3, 15, 30)))
+        );
+        assert_eq!(
+            split_trailing_date("Stand-up wednesday", now),
+            Some(("Stand-up", at(8, 9, 0)))
+        );
+        assert_eq!(
+            split_trailing_date("Stretch 9am", now),
+            Some(("Stretch", at(2, 9, 0)))
+        );
+
+        // Passed already, not a date, or nothing left for the label
+        assert_eq!(split_trailing_date("Coffee today 8am", now), None);
+        assert_eq!(split_trailing_date("Buy 2", now), None);
+        assert_eq!(split_trailing_date("Wed", now), None);
+        assert_eq!(split_trailing_date("Happy sun", now), None);
+        assert_eq!(split_trailing_date("tomorrow", now), None);
+    }
+}
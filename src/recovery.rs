@@ -0,0 +1,146 @@
+//! Salvaging saved state that no longer parses as a whole, e.g. after a field changed type
+//! between versions, by keeping whichever parts still parse on their own.
+
+use serde::de::DeserializeOwned;
+
+/// Parses `text` as the RON struct `T`, dropping fields that don't parse. A field that fails
+/// is offered to `salvage`, which can return replacement RON for it, e.g. with its own broken
+/// parts dropped. `T` must be `#[serde(default)]`, so each field can be tried by itself.
+pub fn lenient_struct<T: DeserializeOwned>(
+    text: &str,
+    salvage: &dyn Fn(&str, &str) -> Option<String>,
+) -> Option<T> {
+    let mut kept = vec![];
+    for (name, value) in struct_fields(text)? {
+        if ron::from_str::<T>(&format!("({name}:{value})")).is_ok() {
+            kept.push(format!("{name}:{value}"));
+        } else if let Some(value) = salvage(name, value) {
+            kept.push(format!("{name}:{value}"));
+        } else {
+            log::warn!("Dropping unreadable saved field `{name}`");
+        }
+    }
+    ron::from_str(&format!("({})", kept.join(","))).ok()
+}
+
+/// Parses `text` as a RON list of `T`, dropping the items that don't parse.
+pub fn lenient_list<T: DeserializeOwned>(text: &str) -> Option<Vec<T>> {
+    let body = text.trim().strip_prefix('[')?.strip_suffix(']')?;
+    let items = split_top_level(body)
+        .into_iter()
+        .filter_map(|item| match ron::from_str(item) {
+            Ok(item) => Some(item),
+            Err(err) => {
+                log::warn!("Dropping an unreadable saved item: {err}");
+                None
+            }
+        })
+        .collect();
+    Some(items)
+}
+
+/// The `name: value` pairs of a RON struct like `(a: 1, b: (2, 3))`.
+fn struct_fields(text: &str) -> Option<Vec<(&str, &str)>> {
+    let text = text.trim();
+    // Skip the struct's name, if it was written with one
+    let body = text[text.find('(')?..]
+        .strip_prefix('(')?
+        .strip_suffix(')')?;
+    split_top_level(body)
+        .into_iter()
+        .map(|field| {
+            let (name, value) = field.split_once(':')?;
+            Some((name.trim(), value.trim()))
+        })
+        .collect()
+}
+
+/// Splits at the commas that aren't nested inside brackets or strings, skipping empty items
+/// like the one after a trailing comma.
+fn split_top_level(body: &str) -> Vec<&str> {
+    let mut items = vec![];
+    let mut depth = 0_usize;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (i, c) in body.char_indices() {
+        if let Some(open) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == open {
+                quote = None;
+            }
+            continue;
+        }
+
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                items.push(&body[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&body[start..]);
+
+    items
+        .into_iter()
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default, serde::Deserialize)]
+    #[serde(default)]
+    struct Saved {
+        name: String,
+        counts: Vec<u32>,
+        size: u32,
+    }
+
+    #[test]
+    fn split_ignores_nested_and_quoted_commas() {
+        assert_eq!(
+            split_top_level(r#"a: (1, 2), b: "x, \"y\"", c: [3],"#),
+            ["a: (1, 2)", r#"b: "x, \"y\"""#, "c: [3]"]
+        );
+    }
+
+    #[test]
+    fn lenient_struct_drops_only_broken_fields() {
+        let saved: Saved =
+            lenient_struct(r#"(name: "doge", counts: [1, 2], size: "big")"#, &|_, _| {
+                None
+            })
+            .unwrap();
+
+        assert_eq!(saved.name, "doge");
+        assert_eq!(saved.counts, [1, 2]);
+        assert_eq!(saved.size, 0);
+    }
+
+    #[test]
+    fn salvage_can_repair_a_field() {
+        let saved: Saved = lenient_struct(
+            r#"(name: "doge", counts: [1, "two", 3])"#,
+            &|name, value| {
+                let counts = (name == "counts").then(|| lenient_list::<u32>(value))??;
+                ron::to_string(&counts).ok()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(saved.name, "doge");
+        assert_eq!(saved.counts, [1, 3]);
+    }
+}
@@ -0,0 +1,216 @@
+//! Repeat rules for stickies, written in a small subset of RFC 5545's RRULE syntax, e.g.
+//! `FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR` for every weekday or `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO`
+//! for every other Monday.
+
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, TimeZone, Weekday};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Recurrence {
+    Daily {
+        interval: u32,
+    },
+    /// On each of `days` in every `interval`th week. No days means the weekday it was done on.
+    Weekly {
+        interval: u32,
+        days: Vec<Weekday>,
+    },
+    /// On the same day of the month, or the month's last day if it's shorter.
+    Monthly {
+        interval: u32,
+    },
+}
+
+impl Recurrence {
+    /// The first occurrence after `done`: the start of the next day the rule lands on, in
+    /// `done`'s zone.
+    pub fn next_after<Z: TimeZone>(&self, done: DateTime<Z>) -> DateTime<Z> {
+        let day = done.date_naive();
+        let next = match self {
+            Self::Daily { interval } => day + Days::new(u64::from(*interval)),
+            Self::Weekly { interval, days } => next_weekly(day, *interval, days),
+            Self::Monthly { interval } => day + Months::new(*interval),
+        };
+        start_of_day(next, &done.timezone())
+    }
+}
+
+fn next_weekly(day: NaiveDate, interval: u32, days: &[Weekday]) -> NaiveDate {
+    let days = if days.is_empty() {
+        vec![day.weekday()]
+    } else {
+        days.to_vec()
+    };
+    let on_rule = |d: &NaiveDate| days.contains(&d.weekday());
+
+    // Later this week counts, otherwise skip ahead to the first matching day `interval` weeks on
+    let week_start = day - Days::new(u64::from(day.weekday().num_days_from_monday()));
+    let later_this_week = (1..7 - u64::from(day.weekday().num_days_from_monday()))
+        .map(|offset| day + Days::new(offset))
+        .find(on_rule);
+    later_this_week.unwrap_or_else(|| {
+        let next_week = week_start + Days::new(7 * u64::from(interval));
+        (0..7)
+            .map(|offset| next_week + Days::new(offset))
+            .find(on_rule)
+            .unwrap_or(next_week)
+    })
+}
+
+fn start_of_day<Z: TimeZone>(day: NaiveDate, zone: &Z) -> DateTime<Z> {
+    let midnight = day.and_hms_opt(0, 0, 0).unwrap_or_default();
+    // Midnight can be skipped by a DST change, in which case fall back to the UTC reading
+    zone.from_local_datetime(&midnight)
+        .earliest()
+        .unwrap_or_else(|| zone.from_utc_datetime(&midnight))
+}
+
+impl std::str::FromStr for Recurrence {
+    type Err = ParseError;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let rule = rule.trim();
+        let rule = rule.strip_prefix("RRULE:").unwrap_or(rule);
+
+        let mut freq = None;
+        let mut interval = 1;
+        let mut days = vec![];
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| ParseError(format!("expected KEY=VALUE, found `{part}`")))?;
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => freq = Some(value.to_ascii_uppercase()),
+                "INTERVAL" => {
+                    interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| ParseError(format!("invalid INTERVAL `{value}`")))?;
+                }
+                "BYDAY" => {
+                    days = value
+                        .split(',')
+                        .map(parse_weekday)
+                        .collect::<Result<_, _>>()?;
+                }
+                _ => return Err(ParseError(format!("{key} isn't supported"))),
+            }
+        }
+
+        if !days.is_empty() && freq.as_deref() != Some("WEEKLY") {
+            return Err(ParseError("BYDAY only works with FREQ=WEEKLY".to_owned()));
+        }
+        match freq.as_deref() {
+            Some("DAILY") => Ok(Self::Daily { interval }),
+            Some("WEEKLY") => Ok(Self::Weekly { interval, days }),
+            Some("MONTHLY") => Ok(Self::Monthly { interval }),
+            Some(freq) => Err(ParseError(format!("FREQ={freq} isn't supported"))),
+            None => Err(ParseError("FREQ is required".to_owned())),
+        }
+    }
+}
+
+fn parse_weekday(day: &str) -> Result<Weekday, ParseError> {
+    match day.trim().to_ascii_uppercase().as_str() {
+        "MO" => Ok(Weekday::Mon),
+        "TU" => Ok(Weekday::Tue),
+        "WE" => Ok(Weekday::Wed),
+        "TH" => Ok(Weekday::Thu),
+        "FR" => Ok(Weekday::Fri),
+        "SA" => Ok(Weekday::Sat),
+        "SU" => Ok(Weekday::Sun),
+        _ => Err(ParseError(format!("unknown day `{day}`"))),
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseError(String);
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: &str) -> DateTime<chrono::Local> {
+        let day = NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap();
+        start_of_day(day, &chrono::Local) + chrono::Duration::hours(15)
+    }
+
+    fn next(rule: &str, done: &str) -> NaiveDate {
+        let recurrence: Recurrence = rule.parse().unwrap();
+        recurrence.next_after(date(done)).date_naive()
+    }
+
+    fn day(day: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn parses_supported_rules() {
+        assert_eq!(
+            "RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO"
+                .parse::<Recurrence>()
+                .unwrap(),
+            Recurrence::Weekly {
+                interval: 2,
+                days: vec![Weekday::Mon]
+            }
+        );
+        assert!("FREQ=YEARLY".parse::<Recurrence>().is_err());
+        assert!("FREQ=DAILY;BYDAY=MO".parse::<Recurrence>().is_err());
+        assert!("FREQ=DAILY;INTERVAL=0".parse::<Recurrence>().is_err());
+        assert!("INTERVAL=2".parse::<Recurrence>().is_err());
+    }
+
+    #[test]
+    fn weekdays_skip_the_weekend() {
+        let weekdays = "FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR";
+        // 2024-05-09 is a Thursday
+        assert_eq!(next(weekdays, "2024-05-09"), day("2024-05-10"));
+        assert_eq!(next(weekdays, "2024-05-10"), day("2024-05-13"));
+        assert_eq!(next(weekdays, "2024-05-11"), day("2024-05-13"));
+    }
+
+    #[test]
+    fn every_other_week_skips_a_week() {
+        // Done on Monday 2024-05-06, so the next is two Mondays on
+        assert_eq!(
+            next("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO", "2024-05-06"),
+            day("2024-05-20")
+        );
+        assert_eq!(
+            next("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR", "2024-05-06"),
+            day("2024-05-10")
+        );
+    }
+
+    #[test]
+    fn monthly_clamps_to_short_months() {
+        assert_eq!(next("FREQ=MONTHLY", "2024-01-31"), day("2024-02-29"));
+        assert_eq!(
+            next("FREQ=DAILY;INTERVAL=3", "2024-01-31"),
+            day("2024-02-03")
+        );
+    }
+
+    #[test]
+    fn reopens_at_midnight_in_the_given_zone() {
+        let zone = chrono_tz::America::New_York;
+        let daily: Recurrence = "FREQ=DAILY".parse().unwrap();
+        let done = |day: &str| {
+            let day = NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap();
+            start_of_day(day, &zone) + chrono::Duration::hours(15)
+        };
+
+        // Clocks go forward on 2024-03-10, so midnight is an hour closer to UTC after
+        let next = daily.next_after(done("2024-03-09"));
+        assert_eq!(next.to_rfc3339(), "2024-03-10T00:00:00-05:00");
+        let next = daily.next_after(done("2024-03-10"));
+        assert_eq!(next.to_rfc3339(), "2024-03-11T00:00:00-04:00");
+    }
+}
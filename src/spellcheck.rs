@@ -0,0 +1,183 @@
+//! Spotting misspelled words in sticky labels, and suggesting what they might have meant.
+//!
+//! Words are checked against an English word list bundled with the app, so it works the same
+//! everywhere, web included. The list is expanded from the en_US Hunspell dictionary of SCOWL
+//! (http://wordlist.aspell.net), leaving out possessives, which are told apart by their "'s".
+
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    sync::{Mutex, OnceLock},
+};
+
+/// The bundled word list, lowercased, with one word per line.
+const WORDS: &str = include_str!("../assets/words.txt");
+/// How many suggestions are offered for a word.
+const MAX_SUGGESTIONS: usize = 5;
+/// How many edits away from a misspelling a suggestion can be.
+const MAX_DISTANCE: usize = 2;
+
+pub struct Dictionary {
+    /// Lowercased.
+    words: HashSet<String>,
+    /// Suggestions already found, by lowercased misspelling, since finding them means
+    /// comparing against every word.
+    suggestions: Mutex<HashMap<String, Vec<String>>>,
+}
+
+/// The bundled dictionary, built once on first use.
+pub fn dictionary() -> &'static Dictionary {
+    static DICTIONARY: OnceLock<Dictionary> = OnceLock::new();
+    DICTIONARY.get_or_init(|| Dictionary::new(WORDS))
+}
+
+impl Dictionary {
+    /// A dictionary of `text`'s lines.
+    pub fn new(text: &str) -> Self {
+        Self {
+            words: text
+                .lines()
+                .map(str::trim)
+                .filter(|word| !word.is_empty())
+                .map(str::to_lowercase)
+                .collect(),
+            suggestions: Mutex::default(),
+        }
+    }
+
+    /// The byte ranges of the words in `text` that aren't in the dictionary, by themselves or
+    /// before an "'s". Tags, and words with digits or of a single letter, are never flagged.
+    pub fn misspelled(&self, text: &str) -> Vec<Range<usize>> {
+        words(text)
+            .filter(|range| {
+                let word = &text[range.clone()];
+                let tag = text[..range.start].ends_with('#');
+                let checked = !tag
+                    && word.chars().count() > 1
+                    && word.chars().all(|c| c.is_alphabetic() || c == '\'');
+                checked && !self.knows(&word.to_lowercase())
+            })
+            .collect()
+    }
+
+    /// Whether `word`, lowercased, is in the dictionary, or is the possessive of a word that is.
+    fn knows(&self, word: &str) -> bool {
+        self.words.contains(word)
+            || word
+                .strip_suffix("'s")
+                .is_some_and(|word| self.words.contains(word))
+    }
+
+    /// The closest words to `word`, nearest first, capitalized if `word` is.
+    pub fn suggest(&self, word: &str) -> Vec<String> {
+        let lowercase = word.to_lowercase();
+        let mut cache = self
+            .suggestions
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        let suggestions = cache.entry(lowercase.clone()).or_insert_with(|| {
+            let len = lowercase.chars().count();
+            let mut close: Vec<(usize, &String)> = self
+                .words
+                .iter()
+                .filter(|other| other.chars().count().abs_diff(len) <= MAX_DISTANCE)
+                .filter_map(|other| {
+                    let distance = edit_distance(&lowercase, other);
+                    (distance <= MAX_DISTANCE).then_some((distance, other))
+                })
+                .collect();
+            close.sort();
+            close
+                .into_iter()
+                .take(MAX_SUGGESTIONS)
+                .map(|(_, other)| other.clone())
+                .collect()
+        });
+
+        let capitalized = word.chars().next().is_some_and(char::is_uppercase);
+        suggestions
+            .iter()
+            .map(|suggestion| {
+                let mut chars = suggestion.chars();
+                match chars.next() {
+                    Some(first) if capitalized => first.to_uppercase().chain(chars).collect(),
+                    _ => suggestion.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// The byte ranges of the words in `text`, which are runs of letters, digits, and apostrophes
+/// within them.
+fn words(text: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut start = None;
+    let mut chars = text.char_indices().peekable();
+    std::iter::from_fn(move || loop {
+        let Some((i, c)) = chars.next() else {
+            return start.take().map(|start| start..text.len());
+        };
+        let next_is_word = chars.peek().is_some_and(|(_, next)| next.is_alphanumeric());
+        let in_word = c.is_alphanumeric() || (c == '\'' && start.is_some() && next_is_word);
+        match (in_word, start) {
+            (true, None) => start = Some(i),
+            (false, Some(word_start)) => {
+                start = None;
+                return Some(word_start..i);
+            }
+            _ => {}
+        }
+    })
+}
+
+/// How many characters have to be added, removed, swapped for others, or swapped with their
+/// neighbor to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // Rows of the distances between prefixes of `a` and `b`, two back for swaps
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut current = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        before = std::mem::replace(&mut previous, current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_unknown_words_and_suggests_close_ones() {
+        let dictionary = Dictionary::new("feed\nthe\ndog\ndogs\nBob\nisn't\n");
+        let text = "Feed teh dgo, Bob's dog isn't 2x #errand";
+
+        let flagged: Vec<&str> = dictionary
+            .misspelled(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect();
+        assert_eq!(flagged, ["teh", "dgo"]);
+
+        assert_eq!(dictionary.suggest("teh"), ["the"]);
+        assert_eq!(dictionary.suggest("Dgo"), ["Dog", "Dogs"]);
+    }
+
+    #[test]
+    fn bundled_dictionary_knows_inflections() {
+        let text = "Unhappily, the doge's dogs weren't running errands";
+        assert!(dictionary().misspelled(text).is_empty());
+        assert_eq!(dictionary().misspelled("Feed teh doge").len(), 1);
+    }
+}
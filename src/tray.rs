@@ -0,0 +1,108 @@
+//! The app's icon in the system tray (the menu bar on macOS), counting the stickies due today
+//! and adding a sticky without going to the window first.
+//!
+//! Only on Windows and macOS: on Linux the tray needs GTK and its own event loop, so there the
+//! count stays in the window's title alone.
+
+use std::sync::mpsc;
+
+use tray_icon::{
+    menu::{Menu, MenuEvent, MenuId, MenuItem},
+    Icon, TrayIcon, TrayIconBuilder,
+};
+
+/// What's been picked from the tray's menu.
+pub enum TrayAction {
+    AddSticky,
+    Open,
+}
+
+pub struct Tray {
+    icon: TrayIcon,
+    add: MenuId,
+    open: MenuId,
+    events: mpsc::Receiver<MenuEvent>,
+    /// The count last shown, so the tooltip is only set again when it changes.
+    due: Option<usize>,
+}
+
+impl Tray {
+    /// Adds the icon to the tray. `None` if the platform turns it down.
+    pub fn new(ctx: &egui::Context) -> Option<Self> {
+        let add = MenuItem::new("Add a sticky", true, None);
+        let open = MenuItem::new("Open stickies", true, None);
+        let menu = Menu::new();
+        if let Err(err) = menu.append(&add).and_then(|()| menu.append(&open)) {
+            log::warn!("Failed to build the tray menu: {err}");
+            return None;
+        }
+
+        let png =
+            eframe::icon_data::from_png_bytes(include_bytes!("../assets/icon-256.png")).ok()?;
+        let icon = match Icon::from_rgba(png.rgba, png.width, png.height) {
+            Ok(icon) => icon,
+            Err(err) => {
+                log::warn!("Failed to load the tray icon: {err}");
+                return None;
+            }
+        };
+        let icon = match TrayIconBuilder::new()
+            .with_menu(Box::new(menu))
+            .with_icon(icon)
+            .with_tooltip("stickies")
+            .build()
+        {
+            Ok(icon) => icon,
+            Err(err) => {
+                log::warn!("Failed to add the tray icon: {err}");
+                return None;
+            }
+        };
+
+        // The menu is picked from while the window may be idle, so wake it to handle the pick
+        let (events_tx, events) = mpsc::channel();
+        let ctx = ctx.clone();
+        MenuEvent::set_event_handler(Some(move |event| {
+            events_tx.send(event).ok();
+            ctx.request_repaint();
+        }));
+
+        Some(Self {
+            icon,
+            add: add.id().clone(),
+            open: open.id().clone(),
+            events,
+            due: None,
+        })
+    }
+
+    /// Shows `due` stickies as due today, in the tooltip and, on macOS, beside the icon.
+    pub fn set_due(&mut self, due: usize) {
+        if self.due == Some(due) {
+            return;
+        }
+        self.due = Some(due);
+        let text = (due > 0).then(|| format!("{due} due"));
+        self.icon.set_title(text.as_deref());
+        let tooltip = match &text {
+            Some(text) => format!("stickies ({text})"),
+            None => "stickies".to_owned(),
+        };
+        if let Err(err) = self.icon.set_tooltip(Some(tooltip)) {
+            log::warn!("Failed to update the tray icon: {err}");
+        }
+    }
+
+    /// The next thing picked from the menu, if any.
+    pub fn take_action(&self) -> Option<TrayAction> {
+        self.events.try_iter().find_map(|event| {
+            if event.id == self.add {
+                Some(TrayAction::AddSticky)
+            } else if event.id == self.open {
+                Some(TrayAction::Open)
+            } else {
+                None
+            }
+        })
+    }
+}